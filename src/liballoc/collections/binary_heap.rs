@@ -478,6 +478,7 @@ impl<T: Ord> BinaryHeap<T> {
     /// occurs when capacity is exhausted and needs a resize. The resize cost
     /// has been amortized in the previous figures.
     #[stable(feature = "rust1", since = "1.0.0")]
+    #[cfg_attr(not(bootstrap), tx_container(insert))]
     pub fn push(&mut self, item: T) {
         let old_len = self.len();
         self.data.push(item);
@@ -613,6 +613,7 @@ impl<T> LinkedList<T> {
     /// assert_eq!(dl.front().unwrap(), &1);
     /// ```
     #[stable(feature = "rust1", since = "1.0.0")]
+    #[cfg_attr(not(bootstrap), tx_container(insert))]
     pub fn push_front(&mut self, elt: T) {
         self.push_front_node(box Node::new(elt));
     }
@@ -656,6 +657,7 @@ impl<T> LinkedList<T> {
     /// assert_eq!(3, *d.back().unwrap());
     /// ```
     #[stable(feature = "rust1", since = "1.0.0")]
+    #[cfg_attr(not(bootstrap), tx_container(insert))]
     pub fn push_back(&mut self, elt: T) {
         self.push_back_node(box Node::new(elt));
     }
@@ -1314,6 +1314,7 @@ impl<T> VecDeque<T> {
     /// assert_eq!(d.front(), Some(&2));
     /// ```
     #[stable(feature = "rust1", since = "1.0.0")]
+    #[cfg_attr(not(bootstrap), tx_container(insert))]
     pub fn push_front(&mut self, value: T) {
         self.grow_if_necessary();
 
@@ -1337,6 +1338,7 @@ impl<T> VecDeque<T> {
     /// assert_eq!(3, *buf.back().unwrap());
     /// ```
     #[stable(feature = "rust1", since = "1.0.0")]
+    #[cfg_attr(not(bootstrap), tx_container(insert))]
     pub fn push_back(&mut self, value: T) {
         self.grow_if_necessary();
 
@@ -1444,6 +1446,7 @@ impl<T> VecDeque<T> {
     /// assert_eq!(vec_deque, &['a', 'd', 'b', 'c']);
     /// ```
     #[stable(feature = "deque_extras_15", since = "1.5.0")]
+    #[cfg_attr(not(bootstrap), tx_container(insert))]
     pub fn insert(&mut self, index: usize, value: T) {
         assert!(index <= self.len(), "index out of bounds");
         self.grow_if_necessary();
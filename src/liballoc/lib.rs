@@ -111,6 +111,7 @@
 #![feature(str_internals)]
 #![feature(trusted_len)]
 #![feature(try_reserve)]
+#![cfg_attr(not(bootstrap), feature(tx_container))]
 #![feature(unboxed_closures)]
 #![feature(unicode_internals)]
 #![feature(unsize)]
@@ -466,6 +466,20 @@ pub trait Labeller<'a> {
         Style::None
     }
 
+    /// Maps `n` to the cluster it is drawn in, if any. The nodes of a
+    /// cluster are drawn together, in a box labelled by `cluster_label`.
+    /// The implementor is responsible for ensuring that the returned name
+    /// is a valid DOT identifier.
+    fn node_cluster(&'a self, _n: &Self::Node) -> Option<Id<'a>> {
+        None
+    }
+
+    /// Maps `cluster` to a label that will be used in the rendered output.
+    /// The default is the cluster's identifier.
+    fn cluster_label(&'a self, cluster: &Id<'a>) -> LabelText<'a> {
+        LabelStr(cluster.name.clone())
+    }
+
     /// Maps `e` to a style that will be used in the rendered output.
     fn edge_style(&'a self, _e: &Self::Edge) -> Style {
         Style::None
@@ -628,30 +642,26 @@ pub fn render_opts<'a, N, E, G, W>(g: &'a G,
           W: Write
 {
     writeln!(w, "digraph {} {{", g.graph_id().as_slice())?;
+    let mut clusters: Vec<(Id<'a>, Vec<N>)> = Vec::new();
     for n in g.nodes().iter() {
-        write!(w, "    ")?;
-        let id = g.node_id(n);
-
-        let escaped = &g.node_label(n).to_dot_string();
-
-        let mut text = Vec::new();
-        write!(text, "{}", id.as_slice()).unwrap();
-
-        if !options.contains(&RenderOption::NoNodeLabels) {
-            write!(text, "[label={}]", escaped).unwrap();
-        }
-
-        let style = g.node_style(n);
-        if !options.contains(&RenderOption::NoNodeStyles) && style != Style::None {
-            write!(text, "[style=\"{}\"]", style.as_slice()).unwrap();
+        match g.node_cluster(n) {
+            Some(cluster) => {
+                match clusters.iter_mut().find(|(id, _)| id.name == cluster.name) {
+                    Some((_, nodes)) => nodes.push(n.clone()),
+                    None => clusters.push((cluster, vec![n.clone()])),
+                }
+            }
+            None => render_node(g, n, "    ", w, options)?,
         }
+    }
 
-        if let Some(s) = g.node_shape(n) {
-            write!(text, "[shape={}]", &s.to_dot_string()).unwrap();
+    for (cluster, nodes) in &clusters {
+        writeln!(w, "    subgraph cluster_{} {{", cluster.name)?;
+        writeln!(w, "        label={};", g.cluster_label(cluster).to_dot_string())?;
+        for n in nodes {
+            render_node(g, n, "        ", w, options)?;
         }
-
-        writeln!(text, ";").unwrap();
-        w.write_all(&text[..])?;
+        writeln!(w, "    }}")?;
     }
 
     for e in g.edges().iter() {
@@ -681,5 +691,41 @@ pub fn render_opts<'a, N, E, G, W>(g: &'a G,
     writeln!(w, "}}")
 }
 
+fn render_node<'a, N, E, G, W>(g: &'a G,
+                               n: &N,
+                               indent: &str,
+                               w: &mut W,
+                               options: &[RenderOption])
+                               -> io::Result<()>
+    where N: Clone + 'a,
+          E: Clone + 'a,
+          G: Labeller<'a, Node=N, Edge=E> + GraphWalk<'a, Node=N, Edge=E>,
+          W: Write
+{
+    write!(w, "{}", indent)?;
+    let id = g.node_id(n);
+
+    let escaped = &g.node_label(n).to_dot_string();
+
+    let mut text = Vec::new();
+    write!(text, "{}", id.as_slice()).unwrap();
+
+    if !options.contains(&RenderOption::NoNodeLabels) {
+        write!(text, "[label={}]", escaped).unwrap();
+    }
+
+    let style = g.node_style(n);
+    if !options.contains(&RenderOption::NoNodeStyles) && style != Style::None {
+        write!(text, "[style=\"{}\"]", style.as_slice()).unwrap();
+    }
+
+    if let Some(s) = g.node_shape(n) {
+        write!(text, "[shape={}]", &s.to_dot_string()).unwrap();
+    }
+
+    writeln!(text, ";").unwrap();
+    w.write_all(&text[..])
+}
+
 #[cfg(test)]
 mod tests;
@@ -378,3 +378,64 @@ fn badly_formatted_id() {
         Err(..) => {}
     }
 }
+
+struct ClusteredGraph {
+    clusters: Vec<Option<&'static str>>,
+}
+
+impl<'a> Labeller<'a> for ClusteredGraph {
+    type Node = Node;
+    type Edge = (Node, Node);
+    fn graph_id(&'a self) -> Id<'a> {
+        Id::new("clustered").unwrap()
+    }
+    fn node_id(&'a self, n: &Node) -> Id<'a> {
+        id_name(n)
+    }
+    fn node_cluster(&'a self, n: &Node) -> Option<Id<'a>> {
+        self.clusters[*n].map(|cluster| Id::new(cluster).unwrap())
+    }
+}
+
+impl<'a> GraphWalk<'a> for ClusteredGraph {
+    type Node = Node;
+    type Edge = (Node, Node);
+    fn nodes(&'a self) -> Nodes<'a, Node> {
+        (0..self.clusters.len()).collect()
+    }
+    fn edges(&'a self) -> Edges<'a, (Node, Node)> {
+        vec![(0, 2), (1, 3)].into()
+    }
+    fn source(&'a self, edge: &(Node, Node)) -> Node {
+        edge.0
+    }
+    fn target(&'a self, edge: &(Node, Node)) -> Node {
+        edge.1
+    }
+}
+
+#[test]
+fn clusters() {
+    let g = ClusteredGraph { clusters: vec![Some("a"), None, Some("a"), Some("b")] };
+    let mut writer = Vec::new();
+    render(&g, &mut writer).unwrap();
+    let mut r = String::new();
+    Read::read_to_string(&mut &*writer, &mut r).unwrap();
+
+    assert_eq!(r,
+r#"digraph clustered {
+    N1[label="N1"];
+    subgraph cluster_a {
+        label="a";
+        N0[label="N0"];
+        N2[label="N2"];
+    }
+    subgraph cluster_b {
+        label="b";
+        N3[label="N3"];
+    }
+    N0 -> N2[label=""];
+    N1 -> N3[label=""];
+}
+"#);
+}
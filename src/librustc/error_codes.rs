@@ -2288,6 +2288,41 @@ the future, [RFC 2091] prohibits their implementation without a follow-up RFC.
 [RFC 2091]: https://github.com/rust-lang/rfcs/blob/master/text/2091-inline-semantic.md
 "##,
 
+E0745: r##"
+An `.await` or a `yield` was used inside a `transaction` block.
+
+Erroneous code example:
+
+```edition2018,compile_fail,E0745
+#![feature(transactions)]
+# async fn ready() {}
+async fn update() {
+    transaction {
+        ready().await; // error: `.await` is not allowed inside a `transaction` block
+    }
+}
+```
+
+An `async fn` or a generator can be suspended at an `.await` or a `yield` and
+resumed much later, or never. If that happened inside a transaction, the
+transaction would keep its lock while suspended, and every transaction it
+conflicts with would wait for it. Finish the transaction before suspending:
+
+```ignore (needs the `transaction_lock` and `transaction_unlock` lang items)
+#![feature(transactions)]
+# async fn ready() {}
+async fn update() {
+    transaction {
+        // ...
+    }
+    ready().await; // ok!
+}
+```
+
+An `async` block or closure inside a transaction is fine, as it only suspends
+when it is run.
+"##,
+
 ;
 //  E0006, // merged with E0005
 //  E0101, // replaced with E0282
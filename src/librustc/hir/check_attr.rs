@@ -13,7 +13,7 @@ use crate::ty::TyCtxt;
 use crate::ty::query::Providers;
 
 use std::fmt::{self, Display};
-use syntax::{attr, symbol::sym};
+use syntax::{attr, symbol::{kw, sym}};
 use syntax_pos::Span;
 
 #[derive(Copy, Clone, PartialEq)]
@@ -126,7 +126,7 @@ impl Target {
         }
     }
 
-    fn from_impl_item<'tcx>(tcx: TyCtxt<'tcx>, impl_item: &hir::ImplItem) -> Target {
+    pub(crate) fn from_impl_item<'tcx>(tcx: TyCtxt<'tcx>, impl_item: &hir::ImplItem) -> Target {
         match impl_item.kind {
             hir::ImplItemKind::Const(..) => Target::AssocConst,
             hir::ImplItemKind::Method(..) => {
@@ -173,6 +173,12 @@ impl CheckAttrVisitor<'tcx> {
                 self.check_target_feature(attr, span, target)
             } else if attr.check_name(sym::track_caller) {
                 self.check_track_caller(&attr.span, attrs, span, target)
+            } else if attr.check_name(sym::rustc_tx_access) {
+                self.check_tx_access(attr, span, target)
+            } else if attr.check_name(sym::tx_container) {
+                self.check_tx_container(attr, span, target)
+            } else if attr.check_name(sym::tx_task) {
+                self.check_tx_task(attr, span, target)
             } else {
                 true
             };
@@ -327,6 +333,105 @@ impl CheckAttrVisitor<'tcx> {
         }
     }
 
+    /// Checks if a `#[rustc_tx_access]` attribute names an access and is applied to a
+    /// function. Returns `true` if valid.
+    fn check_tx_access(&self, attr: &Attribute, span: &Span, target: Target) -> bool {
+        match target {
+            Target::Fn | Target::Method(_) => {}
+            _ => {
+                self.tcx.sess
+                    .struct_span_err(attr.span, "attribute should be applied to a function")
+                    .span_label(*span, "not a function")
+                    .emit();
+                return false;
+            }
+        }
+        match attr.value_str() {
+            Some(access) if access == sym::read || access == sym::write => true,
+            Some(access) => {
+                self.tcx.sess
+                    .struct_span_err(
+                        attr.span,
+                        &format!("unknown transaction access `{}`", access),
+                    )
+                    .help("use `#[rustc_tx_access = \"read\"]` or \
+                           `#[rustc_tx_access = \"write\"]`")
+                    .emit();
+                false
+            }
+            // The form of the attribute is checked against its template.
+            None => false,
+        }
+    }
+
+    /// Checks if a `#[tx_container]` attribute is applied to a type, or with the role
+    /// of a method to a function. Returns `true` if valid.
+    fn check_tx_container(&self, attr: &Attribute, span: &Span, target: Target) -> bool {
+        match (attr.meta_item_list(), target) {
+            (None, Target::Struct) | (None, Target::Enum) | (None, Target::Union) => true,
+            (Some(list), Target::Fn) | (Some(list), Target::Method(_)) => {
+                let role = match &list[..] {
+                    [role] if role.is_word() => role.name_or_empty(),
+                    _ => kw::Invalid,
+                };
+                match role {
+                    sym::get | sym::insert | sym::project | sym::send | sym::with => true,
+                    _ => {
+                        self.tcx.sess
+                            .struct_span_err(
+                                attr.span,
+                                "malformed `tx_container` attribute on a method",
+                            )
+                            .help("use `#[tx_container(get)]`, `#[tx_container(insert)]`, \
+                                   `#[tx_container(project)]`, `#[tx_container(send)]` or \
+                                   `#[tx_container(with)]`")
+                            .emit();
+                        false
+                    }
+                }
+            }
+            (None, _) => {
+                self.tcx.sess
+                    .struct_span_err(attr.span, "attribute should be applied to a type")
+                    .span_label(*span, "not a struct, enum, or union")
+                    .help("the methods of a container take a role, e.g. `#[tx_container(get)]`")
+                    .emit();
+                false
+            }
+            (Some(_), _) => {
+                self.tcx.sess
+                    .struct_span_err(attr.span, "attribute should be applied to a function")
+                    .span_label(*span, "not a function")
+                    .emit();
+                false
+            }
+        }
+    }
+
+    /// Checks if a `#[tx_task(period = N, priority = N)]` attribute is applied to a
+    /// function with a body, and gives both numbers. Returns `true` if valid.
+    fn check_tx_task(&self, attr: &Attribute, span: &Span, target: Target) -> bool {
+        match target {
+            Target::Fn | Target::Method(MethodKind::Trait { body: true })
+            | Target::Method(MethodKind::Inherent) => {}
+            _ => {
+                self.tcx.sess
+                    .struct_span_err(attr.span, "attribute should be applied to a function")
+                    .span_label(*span, "not a function")
+                    .emit();
+                return false;
+            }
+        }
+        if attr::find_tx_task(attr).is_some() {
+            return true;
+        }
+        self.tcx.sess
+            .struct_span_err(attr.span, "malformed `tx_task` attribute")
+            .help("use `#[tx_task(period = N, priority = N)]`, with a period greater than 0")
+            .emit();
+        false
+    }
+
     /// Checks if the `#[repr]` attributes on `item` are valid.
     fn check_repr(
         &self,
@@ -1099,7 +1099,7 @@ pub fn walk_expr<'v, V: Visitor<'v>>(visitor: &mut V, expression: &'v Expr) {
         ExprKind::Yield(ref subexpression, _) => {
             visitor.visit_expr(subexpression);
         }
-        ExprKind::Lit(_) | ExprKind::Lock | ExprKind::Unlock | ExprKind::Err => {}
+        ExprKind::Lit(_) | ExprKind::Lock | ExprKind::Err => {}
     }
 }
 
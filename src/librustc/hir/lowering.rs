@@ -79,7 +79,7 @@ use syntax::source_map::{respan, ExpnData, ExpnKind, DesugaringKind, Spanned};
 use syntax::symbol::{kw, sym, Symbol};
 use syntax::visit::{self, Visitor};
 use syntax_pos::hygiene::ExpnId;
-use syntax_pos::{Span, DUMMY_SP};
+use syntax_pos::Span;
 const HIR_ID_COUNTER_LOCKED: u32 = 0xFFFFFFFF;
 
 pub struct LoweringContext<'a> {
@@ -118,6 +118,10 @@ pub struct LoweringContext<'a> {
     loop_scopes: Vec<NodeId>,
     is_in_loop_condition: bool,
     is_in_trait_impl: bool,
+
+    /// The span of the innermost `transaction` block of the body being lowered, if
+    /// any. Suspending inside it would hold the transaction's lock, see E0745.
+    transaction_span: Option<Span>,
     is_in_dyn_type: bool,
 
     /// What to do when we encounter either an "anonymous lifetime
@@ -276,6 +280,7 @@ pub fn lower_crate(
         loop_scopes: Vec::new(),
         is_in_loop_condition: false,
         is_in_trait_impl: false,
+        transaction_span: None,
         is_in_dyn_type: false,
         anonymous_lifetime_mode: AnonymousLifetimeMode::PassThrough,
         type_def_lifetime_params: Default::default(),
@@ -2684,54 +2689,52 @@ impl<'a> LoweringContext<'a> {
         bounds.iter().map(|bound| self.lower_param_bound(bound, itctx.reborrow())).collect()
     }
 
-    fn lower_transaction_block(&mut self, b: &Block, targeted_by_break: bool) -> P<hir::Block> {
+    /// Lowers `transaction { <body> }` at `span` to `{ Lock; DropTemps({ <body> }) }`.
+    /// The `Lock` gets the span of the whole block, so the lock and unlock calls built
+    /// from it point at the transaction. There is no explicit unlock statement: MIR
+    /// building schedules the unlock on the scope of this block when it sees the
+    /// `Lock`, so the lock is released on every path out of the block, including
+    /// `return`, `?`, `break` and unwinding.
+    ///
+    /// The body is the tail expression, so the transaction evaluates to the value
+    /// of the body. `DropTemps` drops the body's temporaries (e.g. the guard
+    /// returned by `borrow()`) before the unlock; only the value is moved out.
+    fn lower_transaction_block(
+        &mut self,
+        span: Span,
+        b: &Block,
+        targeted_by_break: bool,
+    ) -> P<hir::Block> {
         let mut stmts = vec![];
 
         let lock_expr = Expr {
             id: self.sess.next_node_id(),
             kind: ExprKind::Lock,
-            span: DUMMY_SP,
+            span,
             attrs: ThinVec::new(),
         };
         let lock_stmt = Stmt {
             id: self.sess.next_node_id(),
             kind: StmtKind::Expr(AstP(lock_expr)),
-            span: DUMMY_SP,
+            span,
         };
         let lowered_lock_stmt = self.lower_stmt(&lock_stmt);
 
         info!("[STM] added lock stmt {:#?}", lock_stmt);
         stmts.extend(lowered_lock_stmt);
 
+        let prev_transaction_span = self.transaction_span.replace(b.span);
         let body_block = self.lower_block(b, targeted_by_break);
+        self.transaction_span = prev_transaction_span;
         let body_expr = self.expr_block(body_block, ThinVec::new());
-        let body_stmt = self.stmt_expr(b.span, body_expr);
-
-        info!("[STM] added block stmt {:#?}", body_stmt);
-        stmts.push(body_stmt);
-
-        let unlock_expr = Expr {
-            id: self.sess.next_node_id(),
-            kind: ExprKind::Unlock,
-            span: DUMMY_SP,
-            attrs: ThinVec::new(),
-        };
-
-        let unlock_stmt = Stmt {
-            id: self.sess.next_node_id(),
-            kind: StmtKind::Expr(AstP(unlock_expr.clone())),
-            span: DUMMY_SP,
-        };
-        let lowered_unlock_stmt = self.lower_stmt(&unlock_stmt);
-
+        let tail_expr = self.expr_drop_temps(b.span, P(body_expr), ThinVec::new());
 
-        info!("[STM] added my unlock stmt {:#?}", unlock_stmt);
-        stmts.extend(lowered_unlock_stmt);
+        info!("[STM] added tail expr {:#?}", tail_expr);
 
         P(hir::Block {
             hir_id: self.next_id(),
             stmts: stmts.into(),
-            expr: None,
+            expr: Some(P(tail_expr)),
             rules: self.lower_block_check_mode(&b.rules),
             span: b.span, // TODO: new span??
             targeted_by_break,
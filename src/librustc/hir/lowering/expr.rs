@@ -117,10 +117,9 @@ impl LoweringContext<'_> {
                                                       opt_label.is_some()),
                                                       self.lower_label(opt_label))
             }
-            ExprKind::TransactionBlock(ref body) => self.lower_expr_txn_block(body),
-            // STM - make hir lock/unlock
+            ExprKind::TransactionBlock(ref body) => self.lower_expr_txn_block(e.span, body),
+            // STM - make hir lock
             ExprKind::Lock => hir::ExprKind::Lock,
-            ExprKind::Unlock => hir::ExprKind::Unlock,
             ExprKind::Assign(ref el, ref er) => {
                 hir::ExprKind::Assign(P(self.lower_expr(el)), P(self.lower_expr(er)))
             }
@@ -449,10 +448,10 @@ impl LoweringContext<'_> {
         })
     }
 
-    /// Desugar `transaction { <stmts>; <expr> }`
-    fn lower_expr_txn_block(&mut self, body: &Block) -> hir::ExprKind {
+    /// Desugar `transaction { <stmts>; <expr> }` into a block that evaluates to `<expr>`
+    fn lower_expr_txn_block(&mut self, span: Span, body: &Block) -> hir::ExprKind {
         info!("[STM] lowering tx block");
-        let block = self.lower_transaction_block(body, true).into_inner();
+        let block = self.lower_transaction_block(span, body, true).into_inner();
         info!("[STM] done lowering tx block");
         hir::ExprKind::Block(P(block), None)
     }
@@ -571,6 +570,7 @@ impl LoweringContext<'_> {
                 err.emit();
             }
         }
+        self.check_suspension_in_transaction(await_span, "`.await`");
         let span = self.mark_span_with_reason(
             DesugaringKind::Await,
             await_span,
@@ -1037,6 +1037,24 @@ impl LoweringContext<'_> {
         }
     }
 
+    /// Reject a suspension point inside a `transaction` block: the generator would
+    /// be suspended with the transaction's lock held.
+    fn check_suspension_in_transaction(&self, span: Span, suspension: &str) {
+        if let Some(transaction_span) = self.transaction_span {
+            struct_span_err!(
+                self.sess,
+                span,
+                E0745,
+                "{} is not allowed inside a `transaction` block",
+                suspension,
+            )
+            .span_label(span, "this would suspend while holding the transaction's lock")
+            .span_label(transaction_span, "inside this transaction")
+            .help(&format!("finish the transaction before the {}", suspension))
+            .emit();
+        }
+    }
+
     fn lower_expr_yield(&mut self, span: Span, opt_expr: Option<&Expr>) -> hir::ExprKind {
         match self.generator_kind {
             Some(hir::GeneratorKind::Gen) => {},
@@ -1051,6 +1069,7 @@ impl LoweringContext<'_> {
             },
             None => self.generator_kind = Some(hir::GeneratorKind::Gen),
         }
+        self.check_suspension_in_transaction(span, "`yield`");
 
         let expr = opt_expr
             .as_ref()
@@ -1045,9 +1045,11 @@ impl LoweringContext<'_> {
         f: impl FnOnce(&mut LoweringContext<'_>) -> (HirVec<hir::Param>, hir::Expr),
     ) -> hir::BodyId {
         let prev_gen_kind = self.generator_kind.take();
+        let prev_transaction_span = self.transaction_span.take();
         let (parameters, result) = f(self);
         let body_id = self.record_body(parameters, result);
         self.generator_kind = prev_gen_kind;
+        self.transaction_span = prev_transaction_span;
         body_id
     }
 
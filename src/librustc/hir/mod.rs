@@ -1519,7 +1519,7 @@ impl Expr {
             ExprKind::Struct(..) => ExprPrecedence::Struct,
             ExprKind::Repeat(..) => ExprPrecedence::Repeat,
             ExprKind::Yield(..) => ExprPrecedence::Yield,
-            ExprKind::Lock | ExprKind::Unlock => ExprPrecedence::Err, // TODO(STM): own precedence
+            ExprKind::Lock => ExprPrecedence::Err, // TODO(STM): own precedence
             ExprKind::Err => ExprPrecedence::Err,
         }
     }
@@ -1573,7 +1573,7 @@ impl Expr {
             ExprKind::Yield(..) |
             ExprKind::Cast(..) |
             ExprKind::DropTemps(..) |
-            ExprKind::Lock | ExprKind::Unlock |
+            ExprKind::Lock |
             ExprKind::Err => {
                 false
             }
@@ -1700,9 +1700,9 @@ pub enum ExprKind {
     /// A suspension point for generators (i.e., `yield <expr>`).
     Yield(P<Expr>, YieldSource),
 
-    /// STM - placeholders for lock/unlock
+    /// STM - placeholder for the lock of a transaction. Its unlock is scheduled when
+    /// MIR is built, so that it runs on every path out of the transaction.
     Lock,
-    Unlock,
 
     /// A placeholder for an expression that wasn't syntactically well formed in some way.
     Err,
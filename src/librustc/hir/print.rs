@@ -1440,11 +1440,6 @@ impl<'a> State<'a> {
                 self.s.word("/*LOCK*/");
                 self.pclose();
             }
-            hir::ExprKind::Unlock => {
-                self.popen();
-                self.s.word("/*UNLOCK*/");
-                self.pclose();
-            }            
             hir::ExprKind::Err => {
                 self.popen();
                 self.s.word("/*ERROR*/");
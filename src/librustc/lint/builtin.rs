@@ -527,6 +527,18 @@ declare_lint! {
     };
 }
 
+declare_lint! {
+    pub UNTRANSACTED_ACCESS,
+    Deny,
+    "access to a shared object outside of a transaction"
+}
+
+declare_lint! {
+    pub EMPTY_TRANSACTIONS,
+    Warn,
+    "transactions that use no shared objects"
+}
+
 declare_lint_pass! {
     /// Does nothing as a lint pass, but registers some `Lint`s
     /// that are used by other parts of the compiler.
@@ -594,6 +606,8 @@ declare_lint_pass! {
         MUTABLE_BORROW_RESERVATION_CONFLICT,
         INDIRECT_STRUCTURAL_MATCH,
         SOFT_UNSTABLE,
+        UNTRANSACTED_ACCESS,
+        EMPTY_TRANSACTIONS,
     ]
 }
 
@@ -301,7 +301,7 @@ impl<'a, 'tcx> ExprUseVisitor<'a, 'tcx> {
 
             hir::ExprKind::Continue(..) |
             hir::ExprKind::Lit(..) |
-            hir::ExprKind::Err | hir::ExprKind::Lock | hir::ExprKind::Unlock => {}
+            hir::ExprKind::Err | hir::ExprKind::Lock => {}
 
             hir::ExprKind::Loop(ref blk, _, _) => {
                 self.walk_block(blk);
@@ -15,7 +15,7 @@
 pub use self::LangItem::*;
 
 use crate::hir::def_id::DefId;
-use crate::hir::check_attr::Target;
+use crate::hir::check_attr::{MethodKind, Target};
 use crate::ty::{self, TyCtxt};
 use crate::middle::weak_lang_items;
 use crate::middle::cstore::ExternCrate;
@@ -32,7 +32,7 @@ use crate::hir;
 // So you probably just want to nip down to the end.
 macro_rules! language_item_table {
     (
-        $( $variant:ident, $name:expr, $method:ident, $target:path; )*
+        $( $variant:ident, $name:expr, $method:ident, $target:expr; )*
     ) => {
 
 enum_from_u32! {
@@ -117,12 +117,44 @@ struct LanguageItemCollector<'tcx> {
 
 impl ItemLikeVisitor<'v> for LanguageItemCollector<'tcx> {
     fn visit_item(&mut self, item: &hir::Item) {
-        if let Some((value, span)) = extract(&item.attrs) {
-            let actual_target = Target::from_item(item);
+        self.check_for_lang(Target::from_item(item), item.hir_id, &item.attrs);
+    }
+
+    fn visit_trait_item(&mut self, _trait_item: &hir::TraitItem) {
+        // At present, lang items are always items, not trait items.
+    }
+
+    fn visit_impl_item(&mut self, impl_item: &hir::ImplItem) {
+        // Only the transactional memory lang items are methods, see the end of the table.
+        let actual_target = Target::from_impl_item(self.tcx, impl_item);
+        self.check_for_lang(actual_target, impl_item.hir_id, &impl_item.attrs);
+    }
+}
+
+impl LanguageItemCollector<'tcx> {
+    fn new(tcx: TyCtxt<'tcx>) -> LanguageItemCollector<'tcx> {
+        let mut item_refs = FxHashMap::default();
+
+        $( item_refs.insert($name, ($variant as usize, $target)); )*
+
+        LanguageItemCollector {
+            tcx,
+            items: LanguageItems::new(),
+            item_refs,
+        }
+    }
+
+    fn check_for_lang(
+        &mut self,
+        actual_target: Target,
+        hir_id: hir::HirId,
+        attrs: &[ast::Attribute],
+    ) {
+        if let Some((value, span)) = extract(attrs) {
             match self.item_refs.get(&*value.as_str()).cloned() {
                 // Known lang item with attribute on correct target.
                 Some((item_index, expected_target)) if actual_target == expected_target => {
-                    let def_id = self.tcx.hir().local_def_id(item.hir_id);
+                    let def_id = self.tcx.hir().local_def_id(hir_id);
                     self.collect_item(item_index, def_id);
                 },
                 // Known lang item with attribute on incorrect target.
@@ -154,28 +186,6 @@ impl ItemLikeVisitor<'v> for LanguageItemCollector<'tcx> {
         }
     }
 
-    fn visit_trait_item(&mut self, _trait_item: &hir::TraitItem) {
-        // At present, lang items are always items, not trait items.
-    }
-
-    fn visit_impl_item(&mut self, _impl_item: &hir::ImplItem) {
-        // At present, lang items are always items, not impl items.
-    }
-}
-
-impl LanguageItemCollector<'tcx> {
-    fn new(tcx: TyCtxt<'tcx>) -> LanguageItemCollector<'tcx> {
-        let mut item_refs = FxHashMap::default();
-
-        $( item_refs.insert($name, ($variant as usize, $target)); )*
-
-        LanguageItemCollector {
-            tcx,
-            items: LanguageItems::new(),
-            item_refs,
-        }
-    }
-
     fn collect_item(&mut self, item_index: usize, item_def_id: DefId) {
         // Check for duplicates.
         if let Some(original_def_id) = self.items.items[item_index] {
@@ -414,6 +424,14 @@ language_item_table! {
     TransactionWriteUnlockLangItem, "transaction_write_unlock", transaction_write_unlock, Target::Fn;
     TransactionReadLockLangItem, "transaction_read_lock", transaction_read_lock, Target::Fn;
     TransactionReadUnlockLangItem, "transaction_read_unlock", transaction_read_unlock, Target::Fn;
+    TransactionRegisterLangItem, "transaction_register", transaction_register, Target::Fn;
+
+    // The shared object type and the methods that create and access it. These are
+    // what the def-use analysis in `rustc_mir::transform::transaction` traces.
+    TxPtrLangItem, "tx_ptr", tx_ptr, Target::Struct;
+    TxPtrNewLangItem, "tx_ptr_new", tx_ptr_new, Target::Method(MethodKind::Inherent);
+    TxPtrBorrowLangItem, "tx_ptr_borrow", tx_ptr_borrow, Target::Method(MethodKind::Inherent);
+    TxPtrBorrowMutLangItem, "tx_ptr_borrow_mut", tx_ptr_borrow_mut, Target::Method(MethodKind::Inherent);
 }
 
 impl<'tcx> TyCtxt<'tcx> {
@@ -25,7 +25,7 @@ use crate::ty::{
 
 use polonius_engine::Atom;
 use rustc_index::bit_set::BitMatrix;
-use rustc_data_structures::fx::FxHashSet;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
 use rustc_data_structures::graph::dominators::{dominators, Dominators};
 use rustc_data_structures::graph::{self, GraphPredecessors, GraphSuccessors};
 use rustc_index::vec::{Idx, IndexVec};
@@ -1828,7 +1828,10 @@ impl<V, T> ProjectionElem<V, T> {
 
 /// A unique identifier for a local (and possibly a field of said local) on a
 /// line of code.
-#[derive(Clone, Copy, Debug, Eq, Hash, HashStable, PartialEq)]
+#[derive(
+    Clone, Copy, Debug, Eq, Hash, HashStable, Ord, PartialEq, PartialOrd, RustcEncodable,
+    RustcDecodable
+)]
 pub struct UniqueId {
     pub def_id: DefId,
     pub local: Local,
@@ -1837,13 +1840,15 @@ pub struct UniqueId {
 }
 
 /// A use of a shared object in a transaction.
-#[derive(Clone, Copy, Debug, Eq, Hash, HashStable, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, HashStable, PartialEq, RustcEncodable, RustcDecodable)]
 pub struct TransactionUse {
     pub shared_object: UniqueId,
     pub is_write: bool,
 }
 
-#[derive(Clone, Debug, HashStable)]
+/// The shared objects used by a transaction. Encoded in crate metadata, so the crate
+/// with the program's entry point can analyze the transactions of every crate.
+#[derive(Clone, Debug, HashStable, RustcEncodable, RustcDecodable)]
 pub struct AllocationSet {
     pub lock: UniqueId,
     pub unlock: UniqueId,
@@ -1857,6 +1862,120 @@ pub struct Transaction {
     pub is_write: bool,
 }
 
+/// What a function does with the shared objects passed to it. Callers apply it instead
+/// of tracing the function's body again. Encoded in crate metadata so callers in other
+/// crates can be analyzed without the callee's MIR.
+#[derive(Clone, Debug, Default, Eq, PartialEq, RustcEncodable, RustcDecodable, HashStable)]
+pub struct SharedObjectSummary {
+    /// The arguments whose shared objects the function does anything with.
+    pub args: Vec<ArgSummary>,
+    /// The shared objects allocated by the function, or by the functions it calls, that
+    /// it may return, sorted.
+    pub allocations: Vec<ReturnedAllocation>,
+}
+
+/// What a function does with the shared objects of one of its arguments.
+#[derive(Clone, Debug, Eq, PartialEq, RustcEncodable, RustcDecodable, HashStable)]
+pub struct ArgSummary {
+    pub index: usize,
+    /// The field of the argument that holds the shared objects: a captured variable if
+    /// the argument is the environment of a closure, or a field of a struct or tuple
+    /// (possibly behind references). `None` for the whole argument.
+    pub field: Option<usize>,
+    /// Whether the shared objects are written (`Some(true)`), only read (`Some(false)`)
+    /// or not accessed at all (`None`) outside of the callee's own transactions, that
+    /// is in the caller's transaction.
+    pub access: Option<bool>,
+    /// Whether the shared objects are passed to code that isn't known at compile time.
+    pub escapes: bool,
+    /// Whether the shared objects may be returned.
+    pub returned: bool,
+    /// Uses of the shared objects inside the callee's own transactions (or those of
+    /// the functions it calls), sorted.
+    pub transactions: Vec<SummarizedTransactionUse>,
+}
+
+/// A shared object that a function may return, allocated by it or by a function it calls.
+#[derive(Clone, Debug, Eq, PartialEq, RustcEncodable, RustcDecodable, HashStable)]
+pub struct ReturnedAllocation {
+    /// The shared object as the function knows it: its allocation site, or the call that
+    /// returned it, with the index of the shared object among those of the callee.
+    pub shared_object: UniqueId,
+    /// The number of calls in the call string that identifies `shared_object`.
+    pub depth: usize,
+    /// Whether it is passed to code that isn't known at compile time before it's returned.
+    pub escapes: bool,
+    /// Its uses inside the function's own transactions (or those of the functions it
+    /// calls) before it's returned, sorted.
+    pub transactions: Vec<SummarizedTransactionUse>,
+}
+
+/// A use of the shared objects of an argument inside a transaction of the callee
+/// (or of a function it calls).
+#[derive(
+    Clone, Debug, Eq, PartialEq, Ord, PartialOrd, RustcEncodable, RustcDecodable, HashStable
+)]
+pub struct SummarizedTransactionUse {
+    pub lock: UniqueId,
+    pub unlock: UniqueId,
+    pub is_write: bool,
+}
+
+impl SharedObjectSummary {
+    pub fn is_empty(&self) -> bool {
+        self.args.is_empty() && self.allocations.is_empty()
+    }
+
+    /// Return what the function does with the shared objects of argument `index`, or
+    /// with those in its field `field`.
+    pub fn arg(&self, index: usize, field: Option<usize>) -> Option<&ArgSummary> {
+        self.args.iter().find(|arg| arg.index == index && arg.field == field)
+    }
+}
+
+impl ArgSummary {
+    pub fn is_empty(&self) -> bool {
+        self.access.is_none() && !self.escapes && !self.returned && self.transactions.is_empty()
+    }
+}
+
+/// The strongly connected components of the call graph of the functions the
+/// transaction analysis looks into.
+#[derive(Clone, Debug, Default, HashStable)]
+pub struct CallGraphSccs {
+    pub sccs: Vec<Vec<DefId>>,
+    /// The index of the component of every function.
+    pub scc_of: FxHashMap<DefId, usize>,
+}
+
+/// The transactions of a function and the calls it makes, as found in its validated MIR.
+/// `optimized_mir` steals the validated MIR, so the transaction analysis reads these
+/// instead once a function may have been optimized.
+#[derive(Clone, Debug, Default, HashStable, RustcEncodable, RustcDecodable)]
+pub struct TransactionBlocks {
+    /// The lock call of every outermost transaction, with its unlock calls. The first
+    /// unlock identifies the transaction.
+    pub transactions: Vec<(UniqueId, Vec<UniqueId>)>,
+    /// The lock and unlock calls of the transactions nested in another transaction.
+    pub nested: Vec<UniqueId>,
+    /// The functions called, resolved to the impls they run where possible.
+    pub callees: Vec<DefId>,
+    /// The span of every call.
+    pub call_spans: Vec<(Location, Span)>,
+}
+
+/// How the lock and unlock calls of a transaction are patched.
+#[derive(Clone, Debug, HashStable, RustcEncodable, RustcDecodable)]
+pub struct TransactionLock {
+    pub lock: UniqueId,
+    pub unlocks: Vec<UniqueId>,
+    /// The index of the transaction's conflict set, if it uses any shared objects.
+    pub conflict_set: Option<usize>,
+    pub is_write: bool,
+    /// Whether the transaction needs no lock, so its lock and unlock calls are removed.
+    pub elide: bool,
+}
+
 /// Alias for projections as they appear in places, where the base is a place
 /// and the index is a local.
 pub type PlaceElem<'tcx> = ProjectionElem<Local, Ty<'tcx>>;
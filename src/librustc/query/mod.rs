@@ -109,13 +109,68 @@ rustc_queries! {
         query mir_built(_: DefId) -> &'tcx Steal<mir::Body<'tcx>> {}
 
         /// Get the shared objects for a given DefId.
-        query get_shared_objects(_: DefId) -> Vec<mir::AllocationSet> {}
+        query get_shared_objects(key: DefId) -> Vec<mir::AllocationSet> {
+            cache_on_disk_if { key.is_local() }
+        }
+
+        /// Summarize what a function does with the shared objects passed to it.
+        query shared_object_summary(key: DefId) -> mir::SharedObjectSummary {
+            desc { |tcx| "summarizing the shared objects used by `{}`", tcx.def_path_str(key) }
+            cache_on_disk_if { key.is_local() }
+        }
+
+        /// Find the strongly connected components of the call graph of a crate's
+        /// functions, so that the summaries of recursive functions can be computed
+        /// together.
+        query call_graph_sccs(_: CrateNum) -> Lrc<mir::CallGraphSccs> {
+            desc { "finding the recursive functions of a crate" }
+        }
+
+        /// Summarize the functions of a recursive strongly connected component of the
+        /// call graph together. The component is named by its first function.
+        query recursive_summaries(key: DefId) -> Vec<(DefId, mir::SharedObjectSummary)> {
+            desc { |tcx|
+                "summarizing the shared objects used by the functions recursive with `{}`",
+                tcx.def_path_str(key)
+            }
+            cache_on_disk_if { key.is_local() }
+        }
+
+        /// Get the shared objects used by every transaction in a crate. Transactions of
+        /// other crates appear too, when this crate passes shared objects to them.
+        query crate_transactions(_: CrateNum) -> Vec<mir::AllocationSet> {
+            desc { "collecting the transactions of a crate" }
+        }
+
+        /// Whether the functions of a crate are summarized: the crate was compiled with
+        /// the transaction analysis and could see the shared object type. Functions of
+        /// other crates may do anything with the shared objects passed to them.
+        query has_shared_object_summaries(_: CrateNum) -> bool {
+            desc { "checking whether the functions of a crate are summarized" }
+        }
 
         /// Perform conflict analysis on an entire crate and return the conflict sets.
+        /// The transactions of every crate this crate depends on take part, so that
+        /// the conflict sets of the crate with the program's entry point are global.
         query conflict_analysis(_: CrateNum) -> Vec<Vec<mir::Transaction>> {
             desc { "getting conflict analysis for transactions" }
         }
 
+        /// Find the transactions of a local function and the calls it makes. Unlike
+        /// `mir_validated`, this stays available after `optimized_mir` has run.
+        query transaction_blocks(key: DefId) -> mir::TransactionBlocks {
+            desc { |tcx| "finding the transactions of `{}`", tcx.def_path_str(key) }
+            cache_on_disk_if { key.is_local() }
+        }
+
+        /// Decide how `optimized_mir` patches the transactions of a local function.
+        /// This only changes when the conflict sets of those transactions do, so the
+        /// other functions of the crate keep their `optimized_mir` across edits.
+        query transaction_locks(key: DefId) -> Vec<mir::TransactionLock> {
+            desc { |tcx| "assigning locks to the transactions of `{}`", tcx.def_path_str(key) }
+            cache_on_disk_if { key.is_local() }
+        }
+
         /// Fetch the MIR for a given `DefId` up till the point where it is
         /// ready for const evaluation.
         ///
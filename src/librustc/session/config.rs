@@ -838,6 +838,10 @@ macro_rules! options {
             Some("one of: `disabled`, `trampolines`, or `aliases`");
         pub const parse_symbol_mangling_version: Option<&str> =
             Some("either `legacy` or `v0` (RFC 2603)");
+        pub const parse_transaction_level: Option<&str> =
+            Some("a number from 0 to 3");
+        pub const parse_transaction_dump_format: Option<&str> =
+            Some("`json`");
     }
 
     #[allow(dead_code)]
@@ -1133,6 +1137,20 @@ macro_rules! options {
             };
             true
         }
+
+        fn parse_transaction_level(slot: &mut usize, v: Option<&str>) -> bool {
+            match v.and_then(|s| s.parse().ok()) {
+                Some(level @ 0..=3) => { *slot = level; true }
+                _ => false
+            }
+        }
+
+        fn parse_transaction_dump_format(slot: &mut Option<String>, v: Option<&str>) -> bool {
+            match v {
+                Some("json") => { *slot = Some("json".to_string()); true }
+                _ => false
+            }
+        }
     }
 ) }
 
@@ -1472,8 +1490,20 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
     insert_sideeffect: bool = (false, parse_bool, [TRACKED],
         "fix undefined behavior when a thread doesn't eventually make progress \
          (such as entering an empty infinite loop) by inserting llvm.sideeffect"),
-    transaction_level: usize = (1, parse_uint, [TRACKED],
+    transaction_level: usize = (1, parse_transaction_level, [TRACKED],
         "set the transaction optimization level (0-3, default: 1)"),
+    allocation_context: usize = (0, parse_uint, [TRACKED],
+        "tell apart the shared objects allocated by the same helper function by the last N \
+         calls that returned them (default: 0)"),
+    dump_transactions: Option<String> = (None, parse_transaction_dump_format, [UNTRACKED],
+        "write the transactions of the crate, their shared objects and their locks \
+         to `<crate>.transactions.json` (only `json` is supported)"),
+    dump_conflict_graph: bool = (false, parse_bool, [UNTRACKED],
+        "write the shared objects of the crate and its dependencies, grouped by conflict \
+         set, to `<crate>.conflicts.dot`"),
+    blocking_report: bool = (false, parse_bool, [UNTRACKED],
+        "report how long the tasks marked `#[tx_task]` can be blocked by the transactions \
+         of other tasks"),
 }
 
 pub const fn default_lib_output() -> CrateType {
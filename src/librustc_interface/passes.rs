@@ -949,6 +949,22 @@ fn analysis(tcx: TyCtxt<'_>, cnum: CrateNum) -> Result<()> {
         });
     });
 
+    if sess.opts.debugging_opts.dump_transactions.is_some() {
+        time(sess, "dumping transactions", || {
+            mir::transform::transaction::report::dump_transactions(tcx)
+        });
+    }
+    if sess.opts.debugging_opts.dump_conflict_graph {
+        time(sess, "dumping the conflict graph", || {
+            mir::transform::transaction::conflict_analysis::dump_conflict_graph(tcx)
+        });
+    }
+    if sess.opts.debugging_opts.blocking_report {
+        time(sess, "blocking-time analysis", || {
+            mir::transform::transaction::blocking::report_blocking(tcx)
+        });
+    }
+
     Ok(())
 }
 
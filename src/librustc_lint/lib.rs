@@ -25,6 +25,7 @@ mod error_codes;
 mod nonstandard_style;
 mod redundant_semicolon;
 pub mod builtin;
+mod transactions;
 mod types;
 mod unused;
 mod non_ascii_idents;
@@ -52,6 +53,7 @@ use lint::LintId;
 use redundant_semicolon::*;
 use nonstandard_style::*;
 use builtin::*;
+use transactions::*;
 use types::*;
 use unused::*;
 use non_ascii_idents::*;
@@ -171,6 +173,9 @@ macro_rules! late_lint_mod_passes {
 
             ExplicitOutlivesRequirements: ExplicitOutlivesRequirements,
             InvalidValue: InvalidValue,
+
+            // Depends on the optimized MIR of the functions transactions call
+            BlockingInTransactions: BlockingInTransactions::default(),
         ]);
     )
 }
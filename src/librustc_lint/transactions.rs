@@ -0,0 +1,323 @@
+/// Copyright 2021, MASSACHUSETTS INSTITUTE OF TECHNOLOGY
+/// Subject to FAR 52.227-11 – Patent Rights – Ownership by the Contractor (May 2014)
+/// SPDX-License-Identifier: MIT
+//! Forbid calls that can block, or run for an unbounded time, while a transaction
+//! holds its lock.
+//!
+//! The lock and unlock calls are found in the optimized MIR, after the transaction
+//! pass has patched them, so transactions whose locks were removed aren't checked.
+//! Every call between them is checked, and so is every function they can call whose
+//! MIR is available, down to the known blocking functions of `std` and the allocator,
+//! which are marked `#[rustc_tx_blocking = "<reason>"]`, themselves or through the
+//! impl, trait or module they are in.
+//! Functions marked `#[tx_bounded]` are trusted not to block and aren't searched.
+use rustc::hir::def_id::DefId;
+use rustc::hir::{self, intravisit::FnKind};
+use rustc::lint::{LateContext, LateLintPass, LintArray, LintPass};
+use rustc::mir::{
+    BasicBlock, Body, ClearCrossCrate, NullOp, Rvalue, SourceInfo, StatementKind, TerminatorKind,
+};
+use rustc::ty::subst::SubstsRef;
+use rustc::ty::{self, DefIdTree, Instance, InstanceDef, TyCtxt};
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use std::{cmp, iter, mem};
+use syntax::attr;
+use syntax::symbol::{sym, Symbol};
+use syntax_pos::Span;
+
+declare_lint! {
+    pub BLOCKING_IN_TRANSACTIONS,
+    Deny,
+    "calls that can block or run for an unbounded time inside transactions"
+}
+
+/// Why a function can block: the function it calls that blocks, what that one blocks
+/// on, and the functions the call goes through, starting with the function itself.
+#[derive(Clone)]
+struct Blocking {
+    callee: DefId,
+    reason: Symbol,
+    through: Vec<DefId>,
+}
+
+#[derive(Default)]
+pub struct BlockingInTransactions {
+    /// Whether each function searched so far can block.
+    searched: FxHashMap<DefId, Option<Blocking>>,
+    /// The functions being searched, outermost first. A recursive call to one of them
+    /// is assumed not to block, so recursion ends.
+    stack: Vec<DefId>,
+    /// The lowest index in `stack` of a function whose search was assumed not to block
+    /// by the search going on.
+    assumed: usize,
+    /// Functions found not to block, assuming something about a function that is
+    /// still being searched. They are only settled once it is.
+    provisional: Vec<DefId>,
+}
+
+impl_lint_pass!(BlockingInTransactions => [BLOCKING_IN_TRANSACTIONS]);
+
+impl BlockingInTransactions {
+    /// Return what `def_id` blocks on, if it's a known blocking function: if it, or the
+    /// impl, trait or module it's in, is marked `#[rustc_tx_blocking]`.
+    fn known_blocking(tcx: TyCtxt<'_>, def_id: DefId) -> Option<Symbol> {
+        iter::successors(Some(def_id), |&def_id| tcx.parent(def_id)).find_map(|def_id| {
+            attr::first_attr_value_str_by_name(&tcx.get_attrs(def_id), sym::rustc_tx_blocking)
+        })
+    }
+
+    /// Resolve a call from `caller` to the function that is run, if it's known.
+    fn resolve<'tcx>(
+        tcx: TyCtxt<'tcx>,
+        caller: DefId,
+        callee: DefId,
+        substs: SubstsRef<'tcx>,
+    ) -> Option<DefId> {
+        let substs = tcx.erase_regions(&substs);
+        let instance = Instance::resolve(tcx, tcx.param_env(caller), callee, substs)?;
+        match instance.def {
+            InstanceDef::Item(def_id) => Some(def_id),
+            _ => None,
+        }
+    }
+
+    /// Return why a call to `callee` from `caller` can block, if it can.
+    fn check_call<'tcx>(
+        &mut self,
+        cx: &LateContext<'_, 'tcx>,
+        caller: DefId,
+        callee: DefId,
+        substs: SubstsRef<'tcx>,
+    ) -> Option<Blocking> {
+        let tcx = cx.tcx;
+        if tcx.has_attr(callee, sym::tx_bounded) || is_transaction_call(tcx, callee) {
+            return None;
+        }
+        if let Some(reason) = Self::known_blocking(tcx, callee) {
+            return Some(Blocking { callee, reason, through: vec![] });
+        }
+        let resolved = Self::resolve(tcx, caller, callee, substs)?;
+        if resolved != callee {
+            if tcx.has_attr(resolved, sym::tx_bounded) {
+                return None;
+            }
+            if let Some(reason) = Self::known_blocking(tcx, resolved) {
+                return Some(Blocking { callee: resolved, reason, through: vec![] });
+            }
+        }
+        self.search(cx, resolved)
+    }
+
+    /// Return why `def_id` can block, searching the bodies of the functions it calls.
+    ///
+    /// A function that blocks does so whatever was assumed about the functions on the
+    /// stack, so it's cached at once. A function that doesn't is only cached if it
+    /// didn't depend on a function further up the stack, which may still block;
+    /// otherwise it waits in `provisional` until the first function of its cycle is
+    /// done, and is searched again if that one blocks.
+    fn search<'tcx>(&mut self, cx: &LateContext<'_, 'tcx>, def_id: DefId) -> Option<Blocking> {
+        if let Some(blocking) = self.searched.get(&def_id) {
+            return blocking.clone();
+        }
+        if let Some(index) = self.stack.iter().position(|&searching| searching == def_id) {
+            self.assumed = cmp::min(self.assumed, index);
+            return None;
+        }
+        if !cx.tcx.is_mir_available(def_id) {
+            self.searched.insert(def_id, None);
+            return None;
+        }
+        let index = self.stack.len();
+        let outer_assumed = mem::replace(&mut self.assumed, index);
+        let provisional = self.provisional.len();
+        self.stack.push(def_id);
+
+        let body = cx.tcx.optimized_mir(def_id);
+        let blocks: Vec<BasicBlock> = body.basic_blocks().indices().collect();
+        let blocking = self.check_blocks(cx, def_id, body, &blocks).into_iter().next().map(
+            |(_, mut blocking)| {
+                blocking.through.insert(0, def_id);
+                blocking
+            },
+        );
+
+        self.stack.pop();
+        let assumed = mem::replace(&mut self.assumed, cmp::min(outer_assumed, self.assumed));
+        if blocking.is_some() {
+            self.provisional.truncate(provisional);
+            self.searched.insert(def_id, blocking.clone());
+        } else if assumed < index {
+            self.provisional.push(def_id);
+        } else {
+            for settled in self.provisional.drain(provisional..) {
+                self.searched.insert(settled, None);
+            }
+            self.searched.insert(def_id, None);
+        }
+        blocking
+    }
+
+    /// Return the calls and allocations in `blocks` of `body` that can block.
+    fn check_blocks<'tcx>(
+        &mut self,
+        cx: &LateContext<'_, 'tcx>,
+        def_id: DefId,
+        body: &Body<'tcx>,
+        blocks: &[BasicBlock],
+    ) -> Vec<(SourceInfo, Blocking)> {
+        let tcx = cx.tcx;
+        let mut found = vec![];
+        for &block in blocks {
+            let block_data = &body[block];
+            for statement in &block_data.statements {
+                if let StatementKind::Assign(box (_, Rvalue::NullaryOp(NullOp::Box, _))) =
+                    statement.kind
+                {
+                    if let Some(exchange_malloc) = tcx.lang_items().exchange_malloc_fn() {
+                        let blocking = Blocking {
+                            callee: exchange_malloc,
+                            reason: Symbol::intern("calls the allocator"),
+                            through: vec![],
+                        };
+                        found.push((statement.source_info, blocking));
+                    }
+                }
+            }
+            let terminator = block_data.terminator();
+            if let TerminatorKind::Call { func, .. } = &terminator.kind {
+                if let ty::FnDef(callee, substs) = func.ty(body, tcx).kind {
+                    if let Some(blocking) = self.check_call(cx, def_id, callee, substs) {
+                        found.push((terminator.source_info, blocking));
+                    }
+                }
+            }
+        }
+        found
+    }
+
+    /// Return the blocks run between the lock call ending `lock_block` and the
+    /// unlock calls that end the transaction.
+    fn transaction_blocks<'tcx>(
+        tcx: TyCtxt<'tcx>,
+        body: &Body<'tcx>,
+        lock_block: BasicBlock,
+    ) -> Vec<BasicBlock> {
+        let mut blocks = vec![];
+        let mut visited = FxHashSet::default();
+        let mut worklist: Vec<BasicBlock> =
+            body[lock_block].terminator().successors().cloned().collect();
+        while let Some(block) = worklist.pop() {
+            if !visited.insert(block) {
+                continue;
+            }
+            let terminator = body[block].terminator();
+            if let TerminatorKind::Call { func, .. } = &terminator.kind {
+                if let ty::FnDef(callee, _) = func.ty(body, tcx).kind {
+                    if is_unlock(tcx, callee) {
+                        continue;
+                    }
+                }
+            }
+            blocks.push(block);
+            worklist.extend(terminator.successors().cloned());
+        }
+        blocks.sort();
+        blocks
+    }
+}
+
+fn is_lock(tcx: TyCtxt<'_>, def_id: DefId) -> bool {
+    let lang_items = tcx.lang_items();
+    [lang_items.transaction_lock(), lang_items.transaction_read_lock(),
+     lang_items.transaction_write_lock()].contains(&Some(def_id))
+}
+
+fn is_unlock(tcx: TyCtxt<'_>, def_id: DefId) -> bool {
+    let lang_items = tcx.lang_items();
+    [lang_items.transaction_unlock(), lang_items.transaction_read_unlock(),
+     lang_items.transaction_write_unlock()].contains(&Some(def_id))
+}
+
+fn is_transaction_call(tcx: TyCtxt<'_>, def_id: DefId) -> bool {
+    is_lock(tcx, def_id) || is_unlock(tcx, def_id)
+        || tcx.lang_items().transaction_register() == Some(def_id)
+}
+
+impl<'a, 'tcx> LateLintPass<'a, 'tcx> for BlockingInTransactions {
+    /// Check the transactions of every function and closure.
+    fn check_fn(
+        &mut self,
+        cx: &LateContext<'a, 'tcx>,
+        _: FnKind<'tcx>,
+        _: &'tcx hir::FnDecl,
+        _: &'tcx hir::Body,
+        _: Span,
+        hir_id: hir::HirId,
+    ) {
+        let tcx = cx.tcx;
+        if tcx.lang_items().transaction_lock().is_none() {
+            return;
+        }
+        let def_id = tcx.hir().local_def_id(hir_id);
+        if !tcx.is_mir_available(def_id) {
+            return;
+        }
+        let body = tcx.optimized_mir(def_id);
+        let mut reported = FxHashSet::default();
+        for (lock_block, block_data) in body.basic_blocks().iter_enumerated() {
+            let is_transaction = match &block_data.terminator().kind {
+                TerminatorKind::Call { func, .. } => match func.ty(body, tcx).kind {
+                    ty::FnDef(callee, _) => is_lock(tcx, callee),
+                    _ => false,
+                },
+                _ => false,
+            };
+            if !is_transaction {
+                continue;
+            }
+            let blocks = Self::transaction_blocks(tcx, body, lock_block);
+            for (source_info, blocking) in self.check_blocks(cx, def_id, body, &blocks) {
+                if reported.insert(source_info.span) {
+                    report(cx, body, source_info, &blocking);
+                }
+            }
+        }
+    }
+}
+
+/// Report the call or allocation at `source_info`, in a transaction of `body`.
+fn report(
+    cx: &LateContext<'_, '_>,
+    body: &Body<'_>,
+    source_info: SourceInfo,
+    blocking: &Blocking,
+) {
+    let tcx = cx.tcx;
+    let lint_root = match body.source_scope_local_data {
+        ClearCrossCrate::Set(ref data) => data[source_info.scope].lint_root,
+        ClearCrossCrate::Clear => return,
+    };
+    let callee = tcx.def_path_str(blocking.callee);
+    let mut err = tcx.struct_span_lint_hir(
+        BLOCKING_IN_TRANSACTIONS,
+        lint_root,
+        source_info.span,
+        "operation that can block inside a transaction",
+    );
+    if blocking.through.is_empty() {
+        err.span_label(source_info.span, format!("`{}` {}", callee, blocking.reason));
+    } else {
+        let label = format!("this can reach `{}`, which {}", callee, blocking.reason);
+        err.span_label(source_info.span, label);
+        let through: Vec<String> = blocking
+            .through
+            .iter()
+            .chain(Some(&blocking.callee))
+            .map(|def_id| format!("`{}`", tcx.def_path_str(*def_id)))
+            .collect();
+        err.note(&format!("through the calls {}", through.join(" -> ")));
+    }
+    err.help("transactions must finish in bounded time: move this out of the `transaction` block, \
+              or mark a function that is known not to block `#[tx_bounded]`")
+        .emit();
+}
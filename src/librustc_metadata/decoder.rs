@@ -946,6 +946,13 @@ impl<'a, 'tcx> CrateMetadata {
             .decode((self, tcx))
     }
 
+    crate fn get_shared_object_summary(&self, id: DefIndex) -> mir::SharedObjectSummary {
+        self.root.per_def.shared_object_summary.get(self, id)
+            .filter(|_| !self.is_proc_macro(id))
+            .map(|summary| summary.decode(self))
+            .unwrap_or_default()
+    }
+
     crate fn mir_const_qualif(&self, id: DefIndex) -> u8 {
         match self.kind(id) {
             EntryKind::Const(qualif, _) |
@@ -1171,6 +1178,10 @@ impl<'a, 'tcx> CrateMetadata {
         param_names.decode(self).collect()
     }
 
+    crate fn get_transactions(&self) -> Vec<mir::AllocationSet> {
+        self.root.transactions.decode(self).collect()
+    }
+
     crate fn exported_symbols(
         &self,
         tcx: TyCtxt<'tcx>,
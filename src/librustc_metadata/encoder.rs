@@ -82,6 +82,7 @@ struct PerDefTables<'tcx> {
 
     mir: PerDefTable<Lazy<mir::Body<'tcx>>>,
     promoted_mir: PerDefTable<Lazy<IndexVec<mir::Promoted, mir::Body<'tcx>>>>,
+    shared_object_summary: PerDefTable<Lazy<mir::SharedObjectSummary>>,
 }
 
 macro_rules! encoder_methods {
@@ -472,6 +473,12 @@ impl<'tcx> EncodeContext<'tcx> {
         let exported_symbols = self.encode_exported_symbols(&exported_symbols);
         let exported_symbols_bytes = self.position() - i;
 
+        // Encode the shared objects used by the transactions.
+        i = self.position();
+        let transactions = self.tcx.crate_transactions(LOCAL_CRATE);
+        let transactions = self.lazy(transactions.iter());
+        let transactions_bytes = self.position() - i;
+
         let tcx = self.tcx;
 
         // Encode the items.
@@ -530,6 +537,7 @@ impl<'tcx> EncodeContext<'tcx> {
 
             mir: self.per_def.mir.encode(&mut self.opaque),
             promoted_mir: self.per_def.promoted_mir.encode(&mut self.opaque),
+            shared_object_summary: self.per_def.shared_object_summary.encode(&mut self.opaque),
         };
         let per_def_bytes = self.position() - i;
 
@@ -589,6 +597,8 @@ impl<'tcx> EncodeContext<'tcx> {
             def_path_table,
             impls,
             exported_symbols,
+            transactions,
+            has_shared_object_summaries: tcx.has_shared_object_summaries(LOCAL_CRATE),
             interpret_alloc_index,
             per_def,
         });
@@ -612,6 +622,7 @@ impl<'tcx> EncodeContext<'tcx> {
             println!("         source_map bytes: {}", source_map_bytes);
             println!("            impl bytes: {}", impl_bytes);
             println!("    exp. symbols bytes: {}", exported_symbols_bytes);
+            println!("    transactions bytes: {}", transactions_bytes);
             println!("  def-path table bytes: {}", def_path_table_bytes);
             println!(" proc-macro-data-bytes: {}", proc_macro_data_bytes);
             println!("            item bytes: {}", item_bytes);
@@ -679,6 +690,7 @@ impl EncodeContext<'tcx> {
         self.encode_predicates(def_id);
         self.encode_optimized_mir(def_id);
         self.encode_promoted_mir(def_id);
+        self.encode_shared_object_summary(def_id);
     }
 
     fn encode_enum_variant_ctor(
@@ -923,6 +935,7 @@ impl EncodeContext<'tcx> {
         self.encode_predicates(def_id);
         self.encode_optimized_mir(def_id);
         self.encode_promoted_mir(def_id);
+        self.encode_shared_object_summary(def_id);
     }
 
     fn metadata_output_only(&self) -> bool {
@@ -1006,6 +1019,7 @@ impl EncodeContext<'tcx> {
             self.encode_optimized_mir(def_id);
             self.encode_promoted_mir(def_id);
         }
+        self.encode_shared_object_summary(def_id);
     }
 
     fn encode_fn_param_names_for_body(&mut self, body_id: hir::BodyId)
@@ -1039,6 +1053,18 @@ impl EncodeContext<'tcx> {
         }
     }
 
+    /// Encodes what a function does with the shared objects passed to it. Unlike MIR,
+    /// this is needed for every function, so callers in other crates can be analyzed.
+    fn encode_shared_object_summary(&mut self, def_id: DefId) {
+        debug!("EncodeContext::encode_shared_object_summary({:?})", def_id);
+        if self.tcx.mir_keys(LOCAL_CRATE).contains(&def_id) {
+            let summary = self.tcx.shared_object_summary(def_id);
+            if !summary.is_empty() {
+                record!(self.per_def.shared_object_summary[def_id] <- summary);
+            }
+        }
+    }
+
     // Encodes the inherent implementations of a structure, enumeration, or trait.
     fn encode_inherent_implementations(&mut self, def_id: DefId) {
         debug!("EncodeContext::encode_inherent_implementations({:?})", def_id);
@@ -1302,6 +1328,9 @@ impl EncodeContext<'tcx> {
             self.encode_optimized_mir(def_id);
             self.encode_promoted_mir(def_id);
         }
+        if let hir::ItemKind::Fn(..) = item.kind {
+            self.encode_shared_object_summary(def_id);
+        }
     }
 
     /// Serialize the text of exported macros
@@ -1364,6 +1393,7 @@ impl EncodeContext<'tcx> {
         self.encode_generics(def_id);
         self.encode_optimized_mir(def_id);
         self.encode_promoted_mir(def_id);
+        self.encode_shared_object_summary(def_id);
     }
 
     fn encode_info_for_anon_const(&mut self, def_id: DefId) {
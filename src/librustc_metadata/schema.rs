@@ -195,6 +195,8 @@ crate struct CrateRoot<'tcx> {
     pub def_path_table: Lazy<hir::map::definitions::DefPathTable>,
     pub impls: Lazy<[TraitImpls]>,
     pub exported_symbols: Lazy!([(ExportedSymbol<'tcx>, SymbolExportLevel)]),
+    pub transactions: Lazy<[mir::AllocationSet]>,
+    pub has_shared_object_summaries: bool,
     pub interpret_alloc_index: Lazy<[u32]>,
 
     pub per_def: LazyPerDefTables<'tcx>,
@@ -250,6 +252,7 @@ crate struct LazyPerDefTables<'tcx> {
 
     pub mir: Lazy!(PerDefTable<Lazy!(mir::Body<'tcx>)>),
     pub promoted_mir: Lazy!(PerDefTable<Lazy!(IndexVec<mir::Promoted, mir::Body<'tcx>>)>),
+    pub shared_object_summary: Lazy!(PerDefTable<Lazy<mir::SharedObjectSummary>>),
 }
 
 #[derive(Copy, Clone, RustcEncodable, RustcDecodable)]
@@ -297,7 +297,8 @@ impl<'a, 'tcx> Builder<'a, 'tcx> {
             | ExprKind::Literal { .. }
             | ExprKind::InlineAsm { .. }
             | ExprKind::Yield { .. }
-            | ExprKind::Call { .. } => {
+            | ExprKind::Call { .. }
+            | ExprKind::TransactionLock { .. } => {
                 // these are not places, so we need to make a temporary.
                 debug_assert!(match Category::of(&expr.kind) {
                     Some(Category::Place) => false,
@@ -354,6 +354,7 @@ impl<'a, 'tcx> Builder<'a, 'tcx> {
             | ExprKind::Loop { .. }
             | ExprKind::LogicalOp { .. }
             | ExprKind::Call { .. }
+            | ExprKind::TransactionLock { .. }
             | ExprKind::Field { .. }
             | ExprKind::Deref { .. }
             | ExprKind::Index { .. }
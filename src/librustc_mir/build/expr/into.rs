@@ -271,6 +271,25 @@ impl<'a, 'tcx> Builder<'a, 'tcx> {
             ExprKind::Use { source } => {
                 this.into(destination, block, source)
             }
+            ExprKind::TransactionLock { region_scope, lock, unlock } => {
+                unpack!(block = this.into(destination, block, lock));
+
+                // The unlock callee and arguments are constants, so building
+                // them here emits no code; the call itself is emitted by the
+                // scope machinery wherever `region_scope` is exited.
+                let unlock = this.hir.mirror(unlock);
+                if let ExprKind::Call { fun, args, .. } = unlock.kind {
+                    let fun = unpack!(block = this.as_local_operand(block, fun));
+                    let args = args
+                        .into_iter()
+                        .map(|arg| unpack!(block = this.as_local_operand(block, arg)))
+                        .collect();
+                    this.schedule_transaction_unlock(expr_span, region_scope, fun, args);
+                } else {
+                    span_bug!(expr_span, "transaction unlock is not a call: {:?}", unlock);
+                }
+                block.unit()
+            }
 
             // These cases don't actually need a destination
             ExprKind::Assign { .. }
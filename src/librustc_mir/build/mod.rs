@@ -318,6 +318,12 @@ struct Builder<'a, 'tcx> {
     cached_return_block: Option<BasicBlock>,
     /// Cached block with the `UNREACHABLE` terminator.
     cached_unreachable_block: Option<BasicBlock>,
+
+    /// The callee and arguments of the `transaction_unlock` call emitted for
+    /// each scheduled `DropKind::Unlock`; set by every `transaction` block. The
+    /// calls of all the blocks are the same: their arguments are placeholders that
+    /// are only patched with the transaction's ID after the body is built.
+    transaction_unlock: Option<(Operand<'tcx>, Vec<Operand<'tcx>>)>,
 }
 
 impl<'a, 'tcx> Builder<'a, 'tcx> {
@@ -753,6 +759,7 @@ impl<'a, 'tcx> Builder<'a, 'tcx> {
             cached_resume_block: None,
             cached_return_block: None,
             cached_unreachable_block: None,
+            transaction_unlock: None,
         };
 
         assert_eq!(builder.cfg.start_new_block(), START_BLOCK);
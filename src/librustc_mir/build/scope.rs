@@ -136,7 +136,7 @@ struct DropData {
     /// local to drop
     local: Local,
 
-    /// Whether this is a value Drop or a StorageDead.
+    /// Whether this is a value Drop, a StorageDead or a transaction unlock.
     kind: DropKind,
 
     /// The cached blocks for unwinds.
@@ -164,6 +164,10 @@ struct CachedBlock {
 pub(crate) enum DropKind {
     Value,
     Storage,
+    /// Call `transaction_unlock`, storing its result in `local`. Scheduled by the
+    /// lock call of a `transaction` block so that the lock is released on every
+    /// path out of the block, including unwinding.
+    Unlock,
 }
 
 #[derive(Clone, Debug)]
@@ -263,7 +267,7 @@ impl Scope {
     /// use of optimizations in the MIR generator transform.
     fn needs_cleanup(&self) -> bool {
         self.drops.iter().any(|drop| match drop.kind {
-            DropKind::Value => true,
+            DropKind::Value | DropKind::Unlock => true,
             DropKind::Storage => false,
         })
     }
@@ -486,6 +490,7 @@ impl<'a, 'tcx> Builder<'a, 'tcx> {
             block,
             unwind_to,
             self.arg_count,
+            self.transaction_unlock.as_ref(),
             false, // not generator
             false, // not unwind path
         ));
@@ -579,6 +584,7 @@ impl<'a, 'tcx> Builder<'a, 'tcx> {
                 block,
                 unwind_to,
                 self.arg_count,
+                self.transaction_unlock.as_ref(),
                 false, // not generator
                 false, // not unwind path
             ));
@@ -630,6 +636,7 @@ impl<'a, 'tcx> Builder<'a, 'tcx> {
                 block,
                 unwind_to,
                 self.arg_count,
+                self.transaction_unlock.as_ref(),
                 true, // is generator
                 true, // is cached path
             ));
@@ -732,6 +739,25 @@ impl<'a, 'tcx> Builder<'a, 'tcx> {
         self.schedule_drop(span, region_scope, local, DropKind::Value);
     }
 
+    /// Indicates that the lock taken by a `transaction` block must be
+    /// released on exit from `region_scope` by calling `func(args)`.
+    ///
+    /// The unlock is scheduled like a drop, so every way of leaving the
+    /// scope -- falling off the end, `return`, `?`, `break`, `continue`
+    /// and unwinding -- runs it exactly once.
+    pub fn schedule_transaction_unlock(
+        &mut self,
+        span: Span,
+        region_scope: region::Scope,
+        func: Operand<'tcx>,
+        args: Vec<Operand<'tcx>>,
+    ) {
+        let unit_ty = self.hir.tcx().mk_unit();
+        let local = self.local_decls.push(LocalDecl::new_temp(unit_ty, span));
+        self.transaction_unlock = Some((func, args));
+        self.schedule_drop(span, region_scope, local, DropKind::Unlock);
+    }
+
     /// Indicates that `place` should be dropped on exit from
     /// `region_scope`.
     ///
@@ -759,6 +785,7 @@ impl<'a, 'tcx> Builder<'a, 'tcx> {
                 }
                 false
             }
+            DropKind::Unlock => true,
         };
 
         for scope in self.scopes.iter_mut() {
@@ -934,7 +961,7 @@ impl<'a, 'tcx> Builder<'a, 'tcx> {
                     let top_drop_data = top_scope.drops.pop().unwrap();
 
                     match top_drop_data.kind {
-                        DropKind::Value { .. } => {
+                        DropKind::Value { .. } | DropKind::Unlock => {
                             bug!("Drop scheduled on top of condition variable")
                         }
                         DropKind::Storage => {
@@ -1016,7 +1043,8 @@ impl<'a, 'tcx> Builder<'a, 'tcx> {
 
         for scope in self.scopes.top_scopes(first_uncached) {
             target = build_diverge_scope(&mut self.cfg, scope.region_scope_span,
-                                         scope, target, generator_drop, self.is_generator);
+                                         scope, target, self.transaction_unlock.as_ref(),
+                                         generator_drop, self.is_generator);
         }
 
         target
@@ -1091,6 +1119,7 @@ fn build_scope_drops<'tcx>(
     mut block: BasicBlock,
     last_unwind_to: BasicBlock,
     arg_count: usize,
+    transaction_unlock: Option<&(Operand<'tcx>, Vec<Operand<'tcx>>)>,
     generator_drop: bool,
     is_cached_path: bool,
 ) -> BlockAnd<()> {
@@ -1149,11 +1178,39 @@ fn build_scope_drops<'tcx>(
                     kind: StatementKind::StorageDead(local)
                 });
             }
+            DropKind::Unlock => {
+                let unwind_to = get_unwind_to(scope, is_generator, drop_idx, generator_drop)
+                    .unwrap_or(last_unwind_to);
+
+                let next = cfg.start_new_block();
+                cfg.terminate(block, source_info,
+                              unlock_call(drop_data, transaction_unlock, next, Some(unwind_to)));
+                block = next;
+            }
         }
     }
     block.unit()
 }
 
+/// Builds the `transaction_unlock` call for a scheduled `DropKind::Unlock`.
+fn unlock_call<'tcx>(
+    drop_data: &DropData,
+    transaction_unlock: Option<&(Operand<'tcx>, Vec<Operand<'tcx>>)>,
+    target: BasicBlock,
+    cleanup: Option<BasicBlock>,
+) -> TerminatorKind<'tcx> {
+    let (func, args) = transaction_unlock.unwrap_or_else(|| {
+        span_bug!(drop_data.span, "transaction unlock scheduled without an unlock call")
+    });
+    TerminatorKind::Call {
+        func: func.clone(),
+        args: args.clone(),
+        destination: Some((drop_data.local.into(), target)),
+        cleanup,
+        from_hir_call: false,
+    }
+}
+
 fn get_unwind_to(
     scope: &Scope,
     is_generator: bool,
@@ -1168,7 +1225,7 @@ fn get_unwind_to(
                     span_bug!(drop_data.span, "cached block not present for {:?}", drop_data)
                 }));
             }
-            (false, DropKind::Value) => {
+            (false, DropKind::Value) | (_, DropKind::Unlock) => {
                 return Some(drop_data.cached_block.get(generator_drop).unwrap_or_else(|| {
                     span_bug!(drop_data.span, "cached block not present for {:?}", drop_data)
                 }));
@@ -1183,6 +1240,7 @@ fn build_diverge_scope<'tcx>(cfg: &mut CFG<'tcx>,
                              span: Span,
                              scope: &mut Scope,
                              mut target: BasicBlock,
+                             transaction_unlock: Option<&(Operand<'tcx>, Vec<Operand<'tcx>>)>,
                              generator_drop: bool,
                              is_generator: bool)
                              -> BasicBlock
@@ -1242,25 +1300,26 @@ fn build_diverge_scope<'tcx>(cfg: &mut CFG<'tcx>,
                 *drop_data.cached_block.ref_mut(generator_drop) = Some(target);
             }
             DropKind::Storage => {}
-            DropKind::Value => {
-                let cached_block = drop_data.cached_block.ref_mut(generator_drop);
-                target = if let Some(cached_block) = *cached_block {
+            DropKind::Value | DropKind::Unlock => {
+                let cached_block = drop_data.cached_block.get(generator_drop);
+                target = if let Some(cached_block) = cached_block {
                     storage_deads.clear();
                     target_built_by_us = false;
                     cached_block
                 } else {
                     push_storage_deads(cfg, target, &mut storage_deads);
                     let block = cfg.start_new_cleanup_block();
-                    cfg.terminate(
-                        block,
-                        source_info(drop_data.span),
+                    let kind = if drop_data.kind == DropKind::Unlock {
+                        unlock_call(drop_data, transaction_unlock, target, None)
+                    } else {
                         TerminatorKind::Drop {
                             location: drop_data.local.into(),
                             target,
                             unwind: None
-                        },
-                    );
-                    *cached_block = Some(block);
+                        }
+                    };
+                    cfg.terminate(block, source_info(drop_data.span), kind);
+                    *drop_data.cached_block.ref_mut(generator_drop) = Some(block);
                     target_built_by_us = true;
                     block
                 };
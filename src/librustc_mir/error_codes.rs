@@ -2537,6 +2537,32 @@ information.
 There are some known bugs that trigger this message.
 "##,
 
+E0744: r##"
+A shared object was read or written outside of a `transaction` block.
+
+Erroneous code example:
+
+```ignore (needs a shared object type marked `#[lang = "tx_ptr"]`)
+let counter = TxPtr::new(0);
+*counter.borrow_mut() += 1; // error: shared object accessed outside of a transaction
+```
+
+Nothing prevents another thread from accessing the shared object at the same
+time, so every access has to be inside a transaction:
+
+```ignore (needs a shared object type marked `#[lang = "tx_ptr"]`)
+let counter = TxPtr::new(0);
+transaction {
+    *counter.borrow_mut() += 1; // ok!
+}
+```
+
+This error is the `untransacted_access` lint, which is denied by default. If the
+access is known to be safe, e.g. because no other thread has the shared object
+yet, it can be allowed with `#[allow(untransacted_access)]`, or turned into a
+warning with `#[warn(untransacted_access)]`.
+"##,
+
 ;
 
 //  E0008, // cannot bind by-move into a pattern guard
@@ -767,18 +767,36 @@ fn make_mirror_unadjusted<'a, 'tcx>(
         hir::ExprKind::Lock => {
             info!("[STM] mirroring my lock call");
 
-            let def_id = cx.tcx.require_lang_item(TransactionLockLangItem, None);
-            info!("[STM] def ID is {:?}", def_id);
-
-            create_lock_call(cx, expr, def_id)
-        }
-        hir::ExprKind::Unlock => {
-            info!("[STM] mirroring my unlock call");
-
-            let def_id = cx.tcx.require_lang_item(TransactionUnlockLangItem, None);
-            info!("[STM] def ID is {:?}", def_id);
+            let lock_def_id = cx.tcx.require_lang_item(TransactionLockLangItem, None);
+            let unlock_def_id = cx.tcx.require_lang_item(TransactionUnlockLangItem, None);
+            info!("[STM] def IDs are {:?}, {:?}", lock_def_id, unlock_def_id);
+
+            // The lock is a statement of the block built by `lower_transaction_block`,
+            // so the unlock is scheduled on the scope of that block.
+            let stmt_id = cx.tcx.hir().get_parent_node(expr.hir_id);
+            let block_id = cx.tcx.hir().get_parent_node(stmt_id);
+            let region_scope = region::Scope {
+                id: block_id.local_id,
+                data: region::ScopeData::Node,
+            };
 
-            create_lock_call(cx, expr, def_id)
+            let lock = Expr {
+                temp_lifetime,
+                ty: expr_ty,
+                span: expr.span,
+                kind: create_lock_call(cx, expr, lock_def_id),
+            };
+            let unlock = Expr {
+                temp_lifetime,
+                ty: expr_ty,
+                span: expr.span,
+                kind: create_lock_call(cx, expr, unlock_def_id),
+            };
+            ExprKind::TransactionLock {
+                region_scope,
+                lock: lock.to_ref(),
+                unlock: unlock.to_ref(),
+            }
         }
         hir::ExprKind::Err => unreachable!(),
     };
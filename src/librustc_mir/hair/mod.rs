@@ -271,6 +271,13 @@ pub enum ExprKind<'tcx> {
     Yield {
         value: ExprRef<'tcx>,
     },
+    /// The lock call that opens a `transaction` block. `unlock` is scheduled
+    /// to run on every exit from `region_scope`, the scope of that block.
+    TransactionLock {
+        region_scope: region::Scope,
+        lock: ExprRef<'tcx>,
+        unlock: ExprRef<'tcx>,
+    },
 }
 
 #[derive(Clone, Debug)]
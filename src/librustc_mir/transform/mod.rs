@@ -7,18 +7,26 @@ use crate::{build, shim};
 use rustc::hir;
 use rustc::hir::def_id::{CrateNum, DefId, LOCAL_CRATE};
 use rustc::hir::intravisit::{self, NestedVisitorMap, Visitor};
-use rustc::mir::{AllocationSet, Body, MirPhase, Promoted, Transaction};
+use rustc::mir::{
+    AllocationSet, Body, CallGraphSccs, MirPhase, Promoted, SharedObjectSummary, Transaction,
+    TransactionBlocks,
+};
 use rustc::ty::query::Providers;
 use rustc::ty::steal::Steal;
 use rustc::ty::{InstanceDef, TyCtxt};
 use rustc::util::nodemap::DefIdSet;
+use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::graph::scc::Sccs;
+use rustc_data_structures::graph::vec_graph::VecGraph;
+use rustc_data_structures::sync::Lrc;
 use rustc_index::vec::IndexVec;
 use std::borrow::Cow;
 use std::iter::FromIterator;
 use syntax::ast;
 use syntax_pos::Span;
 use transaction::{
-    conflict_analysis::ConflictAnalysis, make_patches, use_def_analysis::UseDefVisitor,
+    conflict_analysis::{self, ConflictAnalysis}, is_analyzed, make_patches, register_lock_table,
+    transaction_map::TransactionMap, use_def_analysis::UseDefVisitor,
 };
 
 pub mod add_call_guards;
@@ -59,7 +67,14 @@ pub(crate) fn provide(providers: &mut Providers<'_>) {
         is_mir_available,
         promoted_mir,
         conflict_analysis,
+        crate_transactions,
+        has_shared_object_summaries,
         get_shared_objects,
+        shared_object_summary,
+        transaction_blocks,
+        call_graph_sccs,
+        recursive_summaries,
+        transaction_locks: transaction::transaction_locks,
         ..*providers
     };
 }
@@ -133,23 +148,149 @@ fn get_shared_objects(tcx: TyCtxt<'_>, def_id: DefId) -> Vec<AllocationSet> {
     shared_objects
 }
 
-fn conflict_analysis(tcx: TyCtxt<'_>, crate_num: CrateNum) -> Vec<Vec<Transaction>> {
-    info!("[STM] performing CA start");
+fn has_shared_object_summaries(tcx: TyCtxt<'_>, crate_num: CrateNum) -> bool {
+    assert_eq!(crate_num, LOCAL_CRATE);
+    tcx.lang_items().tx_ptr().is_some() && tcx.sess.opts.debugging_opts.transaction_level > 0
+}
+
+fn shared_object_summary(tcx: TyCtxt<'_>, def_id: DefId) -> SharedObjectSummary {
+    if !tcx.has_shared_object_summaries(LOCAL_CRATE) || !is_analyzed(tcx, def_id) {
+        return SharedObjectSummary::default();
+    }
+    let sccs = tcx.call_graph_sccs(LOCAL_CRATE);
+    let scc = &sccs.sccs[sccs.scc_of[&def_id]];
+    if scc.len() > 1 || tcx.transaction_blocks(def_id).callees.contains(&def_id) {
+        let summaries = tcx.recursive_summaries(scc[0]);
+        return summaries.into_iter().find(|(member, _)| *member == def_id).unwrap().1;
+    }
+    let (body, _) = tcx.mir_validated(def_id);
+    let summary = UseDefVisitor::summarize(&body.borrow(), def_id, tcx, &Default::default());
+    debug!("[STM] summary of {:?}: {:?}", def_id, summary);
+    summary
+}
+
+/// Summarize the functions of the recursive component of the call graph whose first
+/// function is `def_id`. Their summaries depend on each other, so every function is
+/// summarized again with the summaries of the others so far, starting from empty ones,
+/// until none changes. Summaries only grow, so this terminates.
+fn recursive_summaries(tcx: TyCtxt<'_>, def_id: DefId) -> Vec<(DefId, SharedObjectSummary)> {
+    let sccs = tcx.call_graph_sccs(LOCAL_CRATE);
+    let scc = &sccs.sccs[sccs.scc_of[&def_id]];
+    let mut summaries: FxHashMap<DefId, SharedObjectSummary> =
+        scc.iter().map(|&member| (member, SharedObjectSummary::default())).collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &member in scc {
+            let (body, _) = tcx.mir_validated(member);
+            let summary = UseDefVisitor::summarize(&body.borrow(), member, tcx, &summaries);
+            if summary != summaries[&member] {
+                debug!("[STM] summary of recursive {:?}: {:?}", member, summary);
+                summaries.insert(member, summary);
+                changed = true;
+            }
+        }
+    }
+    scc.iter().map(|member| (*member, summaries.remove(member).unwrap())).collect()
+}
+
+/// Find the strongly connected components of the call graph of the functions the
+/// transaction analysis looks into. A closure is called by the function it's defined
+/// in, which is where it's passed its captured variables.
+fn call_graph_sccs(tcx: TyCtxt<'_>, crate_num: CrateNum) -> Lrc<CallGraphSccs> {
+    let mut functions: Vec<DefId> = tcx
+        .mir_keys(crate_num)
+        .iter()
+        .filter(|def_id| is_analyzed(tcx, **def_id))
+        .cloned()
+        .collect();
+    functions.sort();
+    let index: FxHashMap<DefId, usize> =
+        functions.iter().enumerate().map(|(i, def_id)| (*def_id, i)).collect();
+
+    let mut edges = vec![];
+    for (caller, &def_id) in functions.iter().enumerate() {
+        let callees = tcx.transaction_blocks(def_id).callees;
+        edges.extend(callees.iter().filter_map(|callee| index.get(callee)).map(|&i| (caller, i)));
+        if tcx.is_closure(def_id) {
+            if let Some(&parent) = tcx.parent(def_id).and_then(|parent| index.get(&parent)) {
+                edges.push((parent, caller));
+            }
+        }
+    }
+    let graph = VecGraph::new(functions.len(), edges);
+    let components: Sccs<usize, usize> = Sccs::new(&graph);
+
+    let mut sccs = vec![vec![]; components.num_sccs()];
+    let mut scc_of = FxHashMap::default();
+    for (i, def_id) in functions.into_iter().enumerate() {
+        let scc = components.scc(i);
+        sccs[scc].push(def_id);
+        scc_of.insert(def_id, scc);
+    }
+    Lrc::new(CallGraphSccs { sccs, scc_of })
+}
+
+fn transaction_blocks(tcx: TyCtxt<'_>, def_id: DefId) -> TransactionBlocks {
+    if !is_analyzed(tcx, def_id) {
+        return TransactionBlocks::default();
+    }
+    let (body, _) = tcx.mir_validated(def_id);
+    TransactionMap::blocks(def_id, &body.borrow(), tcx)
+}
+
+fn crate_transactions(tcx: TyCtxt<'_>, crate_num: CrateNum) -> Vec<AllocationSet> {
+    let analyzed: Vec<DefId> = tcx
+        .mir_keys(crate_num)
+        .iter()
+        .filter(|def_id| is_analyzed(tcx, **def_id))
+        .cloned()
+        .collect();
+    // Everything the analysis needs from the validated MIR of a function is kept in
+    // these queries, so `optimized_mir` can steal it once this query has run.
+    for &def_id in &analyzed {
+        tcx.ensure().transaction_blocks(def_id);
+        tcx.ensure().shared_object_summary(def_id);
+    }
 
     let mut all = vec![];
-    for def_id in tcx.mir_keys(crate_num) {
+    // Level 0 takes a global lock without analyzing anything.
+    if tcx.sess.opts.debugging_opts.transaction_level == 0 {
+        return all;
+    }
+    for def_id in analyzed {
         info!("[STM] considering {:?}", def_id);
-        if !tcx.is_const_fn(def_id.clone()) {
-            let shared_objs = tcx.get_shared_objects(*def_id);
-            all.extend(shared_objs);
-        }
+        all.extend(tcx.get_shared_objects(def_id));
     }
+    all
+}
+
+fn conflict_analysis(tcx: TyCtxt<'_>, crate_num: CrateNum) -> Vec<Vec<Transaction>> {
+    info!("[STM] performing CA start");
+
+    let all = conflict_analysis::all_transactions(tcx, crate_num);
     info!("[STM] consider all shared objects {:?}", all);
 
     // Perform conflict analysis on all the shared objects here.
     let ca = ConflictAnalysis::new(all).perform();
     info!("[STM] performing CA done");
-    ca.into_iter().map(|hs| Vec::from_iter(hs)).collect()
+
+    // Put the conflict sets in a stable order, so the result is the same when the
+    // transactions are, and the functions that use it aren't patched again.
+    let key = |transaction: &Transaction| {
+        let lock = &transaction.lock;
+        (tcx.def_path_hash(lock.def_id), lock.location, transaction.unlock.location)
+    };
+    let mut conflict_sets: Vec<Vec<Transaction>> = ca
+        .into_iter()
+        .map(|hs| {
+            let mut conflict_set = Vec::from_iter(hs);
+            conflict_set.sort_by_cached_key(|transaction| (key(transaction), transaction.is_write));
+            conflict_set
+        })
+        .collect();
+    conflict_sets.sort_by_cached_key(|conflict_set| key(&conflict_set[0]));
+    conflict_sets
 }
 
 fn mir_built(tcx: TyCtxt<'_>, def_id: DefId) -> &Steal<Body<'_>> {
@@ -357,19 +498,32 @@ fn optimized_mir(tcx: TyCtxt<'_>, def_id: DefId) -> &Body<'_> {
     // execute before we can steal.
     tcx.ensure().mir_borrowck(def_id);
 
-    // conflict analysis uses `mir_validated`, so we have to force it to
-    // execute before we can steal.
-    let mut patches = make_patches(def_id, tcx);
+    // The per-function queries of the transaction analysis use `mir_validated`, so
+    // we have to force them to execute before we can steal. Only these are forced,
+    // not `conflict_analysis`: it changes whenever a transaction moves anywhere in
+    // the crate, and `transaction_locks` keeps that from reaching this function
+    // unless its own locks change.
+    let analyzed = is_analyzed(tcx, def_id);
+    if analyzed {
+        tcx.ensure().transaction_blocks(def_id);
+        tcx.ensure().shared_object_summary(def_id);
+        if tcx.sess.opts.debugging_opts.transaction_level > 0 {
+            tcx.ensure().get_shared_objects(def_id);
+        }
+        tcx.ensure().transaction_locks(def_id);
+    }
 
     let (body, _) = tcx.mir_validated(def_id);
-    // [STM] this causes a huge performance hit.
-    let mut body = (*body.borrow()).clone(); // used to be body.steal()
+    let mut body = body.steal();
 
-    if let Some(patch) = patches.remove(&def_id) {
+    if analyzed {
         info!("[STM] applying patch...");
-        patch.apply(&mut body);
+        make_patches(def_id, tcx, &body).apply(&mut body);
         info!("[STM] applied patch");
     }
+    if tcx.entry_fn(LOCAL_CRATE).map(|(entry, _)| entry) == Some(def_id) {
+        register_lock_table(tcx, &mut body);
+    }
 
     run_optimization_passes(tcx, &mut body, def_id, None);
     tcx.arena.alloc(body)
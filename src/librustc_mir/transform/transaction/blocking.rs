@@ -0,0 +1,196 @@
+/// Copyright 2021, MASSACHUSETTS INSTITUTE OF TECHNOLOGY
+/// Subject to FAR 52.227-11 – Patent Rights – Ownership by the Contractor (May 2014)
+/// SPDX-License-Identifier: MIT
+//! Bound how long the real-time tasks of a program can be blocked by the transactions
+//! of other tasks (`-Z blocking-report`).
+//!
+//! Tasks are the functions marked `#[tx_task(period = N, priority = N)]`, usually the
+//! entry functions of threads. A transaction waits for its lock only while
+//! transactions of other tasks that take the same lock hold it. With phase-fair
+//! reader-writer locks, and one processor per task spinning for its locks, every
+//! other task has at most one request ahead of it, so:
+//!
+//! * a read request waits for at most one writer phase and one reader phase:
+//!   `max(Lw) + max(Lr)`, or nothing if no other task writes;
+//! * a write request waits for the write of every other task that writes, and a reader
+//!   phase before each of them and before its own turn: `sum(max(Lw_j)) + (w + 1) * max(Lr)`,
+//!   where `w` is the number of other tasks that write.
+//!
+//! `L(n)` is the length of the critical section of the transaction with ID `n`.
+//! Transactions that take a mutex (transaction levels 0 and 1) count as writes.
+use super::transaction_map::TransactionMap;
+use super::{call_span, lock_table, transaction_id};
+use rustc::hir::def_id::{DefId, LOCAL_CRATE};
+use rustc::mir::UniqueId;
+use rustc::ty::TyCtxt;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use syntax::attr;
+use syntax::symbol::sym;
+use syntax_pos::Span;
+
+/// A task: a function that runs once per period at a fixed priority.
+struct Task {
+    def_id: DefId,
+    period: u128,
+    priority: u128,
+    /// The transactions a job of the task runs, with the lock they take.
+    requests: Vec<Request>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct Request {
+    id: u32,
+    lock: UniqueId,
+    /// The lock the transaction takes at run time.
+    runtime_lock: usize,
+    is_write: bool,
+}
+
+/// Return the period and the priority of the task `def_id`, if it's marked
+/// `#[tx_task]`. A malformed attribute was reported when attributes were checked.
+fn task_attributes(tcx: TyCtxt<'_>, def_id: DefId) -> Option<(u128, u128)> {
+    let attrs = tcx.get_attrs(def_id);
+    attr::find_by_name(&attrs, sym::tx_task).and_then(attr::find_tx_task)
+}
+
+/// Return the span of the lock call `lock`.
+fn lock_span(tcx: TyCtxt<'_>, lock: &UniqueId) -> Span {
+    call_span(tcx, lock).unwrap_or_else(|| tcx.def_span(lock.def_id))
+}
+
+/// Return `L(n)` for a single transaction, or `max(L(n), ..)` for several.
+fn max_of(requests: &[&Request]) -> String {
+    let mut ids: Vec<u32> = requests.iter().map(|request| request.id).collect();
+    ids.sort();
+    ids.dedup();
+    let lengths: Vec<String> = ids.iter().map(|id| format!("L({})", id)).collect();
+    match &lengths[..] {
+        [length] => length.clone(),
+        _ => format!("max({})", lengths.join(", ")),
+    }
+}
+
+/// Return the bound on how long `request` of `task` waits for the transactions of
+/// the other tasks, or `None` if it never waits.
+fn blocking_bound(tasks: &[Task], task: &Task, request: &Request) -> Option<String> {
+    // The conflicting requests of every other task.
+    let others: Vec<Vec<&Request>> = tasks
+        .iter()
+        .filter(|other| other.def_id != task.def_id)
+        .map(|other| {
+            other.requests.iter().filter(|r| r.runtime_lock == request.runtime_lock).collect()
+        })
+        .collect();
+    let writes: Vec<Vec<&Request>> = others
+        .iter()
+        .map(|requests| requests.iter().filter(|r| r.is_write).cloned().collect::<Vec<_>>())
+        .filter(|writes| !writes.is_empty())
+        .collect();
+    let reads: Vec<&Request> = others.iter().flatten().filter(|r| !r.is_write).cloned().collect();
+
+    let mut terms = vec![];
+    if request.is_write {
+        terms.extend(writes.iter().map(|writes| max_of(writes)));
+        if !reads.is_empty() {
+            match writes.len() {
+                0 => terms.push(max_of(&reads)),
+                w => terms.push(format!("{} * {}", w + 1, max_of(&reads))),
+            }
+        }
+    } else if !writes.is_empty() {
+        terms.push(max_of(&writes.concat()));
+        if !reads.is_empty() {
+            terms.push(max_of(&reads));
+        }
+    }
+    if terms.is_empty() { None } else { Some(terms.join(" + ")) }
+}
+
+pub fn report_blocking(tcx: TyCtxt<'_>) {
+    let level = tcx.sess.opts.debugging_opts.transaction_level;
+    let locks: FxHashMap<u32, usize> = lock_table(tcx).into_iter().collect();
+    // Whether each transaction takes a write lock, as it is patched, or `None` if its lock
+    // calls are removed.
+    let mode_of = |lock: &UniqueId| -> Option<bool> {
+        let transactions = tcx.transaction_locks(lock.def_id);
+        let transaction = transactions.iter().find(|transaction| transaction.lock == *lock)?;
+        match level {
+            _ if transaction.elide => None,
+            0 | 1 => Some(true),
+            _ => Some(transaction.is_write),
+        }
+    };
+
+    let mut tasks = vec![];
+    for &def_id in tcx.mir_keys(LOCAL_CRATE).iter() {
+        let (period, priority) = match task_attributes(tcx, def_id) {
+            Some(attributes) => attributes,
+            None => continue,
+        };
+        let mut requests = vec![];
+        let mut seen = FxHashSet::default();
+        for (lock, _) in TransactionMap::transactions_run_by(tcx, def_id) {
+            if !seen.insert(lock) {
+                continue;
+            }
+            let id = transaction_id(tcx, &lock);
+            // Level 0 takes the global lock 0 for every transaction, and transactions
+            // that use no shared objects take no lock.
+            let request = match (level, mode_of(&lock)) {
+                (_, None) => None,
+                (0, _) => Some(Request { id, lock, runtime_lock: 0, is_write: true }),
+                (_, Some(is_write)) => locks
+                    .get(&id)
+                    .map(|&runtime_lock| Request { id, lock, runtime_lock, is_write }),
+            };
+            requests.extend(request);
+        }
+        requests.sort_by_key(|request| (request.lock.def_id.index, request.lock.location));
+        debug!("[STM] task {:?} runs {} transactions", def_id, requests.len());
+        tasks.push(Task { def_id, period, priority, requests });
+    }
+    tasks.sort_by_key(|task| (task.priority, tcx.def_path_str(task.def_id)));
+
+    for task in &tasks {
+        let msg = format!(
+            "blocking bound of task `{}` (period {}, priority {})",
+            tcx.def_path_str(task.def_id),
+            task.period,
+            task.priority
+        );
+        let mut diag = tcx.sess.diagnostic().span_note_diag(tcx.def_span(task.def_id), &msg);
+        let mut conflicting: Vec<(DefId, Request)> = vec![];
+        for request in &task.requests {
+            let mode = if request.is_write { "write" } else { "read" };
+            let msg = match blocking_bound(&tasks, task, request) {
+                Some(bound) => format!(
+                    "transaction {} ({}, lock {}) waits for at most {}",
+                    request.id, mode, request.runtime_lock, bound
+                ),
+                None => format!("transaction {} ({}) never waits", request.id, mode),
+            };
+            diag.span_note(lock_span(tcx, &request.lock), &msg);
+            for other in tasks.iter().filter(|other| other.def_id != task.def_id) {
+                for other_request in &other.requests {
+                    let blocks = other_request.runtime_lock == request.runtime_lock
+                        && (request.is_write || other_request.is_write);
+                    if blocks && !conflicting.contains(&(other.def_id, *other_request)) {
+                        conflicting.push((other.def_id, *other_request));
+                    }
+                }
+            }
+        }
+        for (other, request) in conflicting {
+            let msg = format!(
+                "it can be blocked by transaction {} of task `{}`, whose critical section \
+                 is `L({})`",
+                request.id,
+                tcx.def_path_str(other),
+                request.id
+            );
+            diag.span_note(lock_span(tcx, &request.lock), &msg);
+        }
+        diag.note("`L(n)` is the length of the critical section of transaction `n`");
+        diag.emit();
+    }
+}
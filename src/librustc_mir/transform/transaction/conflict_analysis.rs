@@ -1,14 +1,43 @@
 /// Copyright 2021, MASSACHUSETTS INSTITUTE OF TECHNOLOGY
 /// Subject to FAR 52.227-11 – Patent Rights – Ownership by the Contractor (May 2014)
 /// SPDX-License-Identifier: MIT
+use super::report::span_of;
+use super::shared_object::catch_all;
+use rustc::hir::def_id::{CrateNum, LOCAL_CRATE};
 use rustc::mir::{AllocationSet, Transaction, UniqueId};
+use rustc::ty::TyCtxt;
 use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_data_structures::unify::{InPlace, UnificationTable, UnifyKey};
+use std::borrow::Cow;
+use std::fs;
+
+/// Return the transactions of `crate_num` and of every crate it depends on.
+pub fn all_transactions(tcx: TyCtxt<'_>, crate_num: CrateNum) -> Vec<AllocationSet> {
+    let mut all = tcx.crate_transactions(crate_num);
+    for &cnum in tcx.crates().iter() {
+        all.extend(tcx.crate_transactions(cnum));
+    }
+    all
+}
+
+/// A shared object, as a key in the disjoint sets of shared objects. The lock table
+/// uses it for conflict sets too.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub(super) struct ObjectKey(u32);
+
+impl UnifyKey for ObjectKey {
+    type Value = ();
+    fn index(&self) -> u32 { self.0 }
+    fn from_index(i: u32) -> ObjectKey { ObjectKey(i) }
+    fn tag() -> &'static str { "ObjectKey" }
+}
 
 pub struct ConflictAnalysis {
     /// Map from every shared object to the transactions that use it.
     vertices: FxHashMap<UniqueId, FxHashSet<Transaction>>,
-    /// Map where vertex u -> every vertex v it connects to
-    edges: FxHashMap<UniqueId, FxHashSet<UniqueId>>,
+    /// Map from every transaction to the shared objects it uses. The conflict graph
+    /// has an edge between every two of them.
+    transactions: FxHashMap<Transaction, FxHashSet<UniqueId>>,
 }
 
 impl ConflictAnalysis {
@@ -18,7 +47,7 @@ impl ConflictAnalysis {
 
         // Create vertices runs in O(K|W|).
         let mut vertices: FxHashMap<UniqueId, FxHashSet<Transaction>> = Default::default();
-        let mut tx_to_objects: FxHashMap<Transaction, FxHashSet<UniqueId>> = Default::default();
+        let mut transactions: FxHashMap<Transaction, FxHashSet<UniqueId>> = Default::default();
         // O(K)
         for AllocationSet { lock, unlock, allocations } in &allocation_sets {
             // O(|W|)
@@ -29,69 +58,193 @@ impl ConflictAnalysis {
                     .entry(transaction_use.shared_object)
                     .or_insert(Default::default())
                     .insert(transaction.clone());
-                tx_to_objects
+                transactions
                     .entry(transaction.clone())
                     .or_insert(Default::default())
                     .insert(transaction_use.shared_object);
             }
         }
 
-        let mut edges: FxHashMap<UniqueId, FxHashSet<UniqueId>> = Default::default();
-
-        // Create edges runs in O(K|W|^2).
-        // O(K)
-        for shared_objects in tx_to_objects.values() {
-            // O(|W|)
-            for u in shared_objects.iter() {
-                // O(|W|)
-                for v in shared_objects.iter() {
-                    if u != v {
-                        debug!("[STM] adding edge {:?} <-> {:?}", u, v);
-                        edges.entry(*u).or_insert(Default::default()).insert(*v);
-                    }
-                }
-            }
-        }
-
         let num_vertices = vertices.len();
         debug!("[STM] {} vertices", num_vertices);
 
-        ConflictAnalysis { vertices, edges }
+        ConflictAnalysis { vertices, transactions }
     }
 
     /// Compute the connected components of the graph to find the
-    /// conflict sets for this program.
+    /// conflict sets for this program. The shared objects of a transaction are
+    /// all in the same component, so rather than building the O(K|W|^2) edges,
+    /// every transaction joins the disjoint sets of its shared objects.
     pub fn perform(&self) -> Vec<FxHashSet<Transaction>> {
-        let mut visited: FxHashSet<UniqueId> = Default::default();
-        let mut conflict_sets: Vec<FxHashSet<Transaction>> = vec![];
+        let mut table: UnificationTable<InPlace<ObjectKey>> = UnificationTable::new();
+        let keys: FxHashMap<UniqueId, ObjectKey> =
+            self.vertices.keys().map(|object| (*object, table.new_key(()))).collect();
+
+        // Union runs in O(K|W| α(|V|)).
+        for shared_objects in self.transactions.values() {
+            let mut shared_objects = shared_objects.iter();
+            if let Some(first) = shared_objects.next() {
+                for object in shared_objects {
+                    debug!("[STM] joining {:?} and {:?}", first, object);
+                    table.union(keys[first], keys[object]);
+                }
+            }
+        }
 
-        // DFS runs in O(|V| + |E|).
-        for v in self.vertices.keys() {
-            if !visited.contains(v) {
-                let mut conflict_set: FxHashSet<Transaction> = Default::default();
-                self.dfs_util(v, &mut visited, &mut conflict_set);
-                conflict_sets.push(conflict_set);
+        let mut conflict_set_of: FxHashMap<ObjectKey, usize> = Default::default();
+        let mut conflict_sets: Vec<FxHashSet<Transaction>> = vec![];
+        for (object, transactions) in &self.vertices {
+            let root = table.find(keys[object]);
+            let next = conflict_sets.len();
+            let i = *conflict_set_of.entry(root).or_insert(next);
+            if i == next {
+                conflict_sets.push(Default::default());
             }
+            conflict_sets[i].extend(transactions.iter().cloned());
         }
 
         conflict_sets
     }
+}
+
+/// Write the conflict graph of the crate to `<crate>.conflicts.dot`
+/// (`-Z dump-conflict-graph`): a node for every shared object, an edge between
+/// shared objects used by the same transaction, and a cluster for every conflict set.
+pub fn dump_conflict_graph(tcx: TyCtxt<'_>) {
+    let analysis = ConflictAnalysis::new(all_transactions(tcx, LOCAL_CRATE));
+
+    // A transaction can be in two conflict sets, once as a reader and once as a writer,
+    // so they are told apart by mode as well.
+    let mut conflict_set_of: FxHashMap<Transaction, usize> = Default::default();
+    for (i, conflict_set) in tcx.conflict_analysis(LOCAL_CRATE).iter().enumerate() {
+        for transaction in conflict_set {
+            conflict_set_of.insert(transaction.clone(), i);
+        }
+    }
+    let locks: FxHashSet<UniqueId> =
+        analysis.vertices.values().flatten().map(|transaction| transaction.lock).collect();
+    let catch_all = catch_all(tcx);
 
-    fn dfs_util(
-        &self,
-        u: &UniqueId,
-        visited: &mut FxHashSet<UniqueId>,
-        conflict_set: &mut FxHashSet<Transaction>,
-    ) {
-        visited.insert(*u);
-        let tx_ids = self.vertices.get(u).unwrap();
-        conflict_set.extend(tx_ids.clone());
-        if let Some(next) = self.edges.get(u) {
-            for v in next {
-                if !visited.contains(v) {
-                    self.dfs_util(v, visited, conflict_set);
+    let mut nodes: Vec<(UniqueId, String)> = analysis
+        .vertices
+        .keys()
+        .map(|object| {
+            let span = span_of(tcx, object).unwrap_or_else(|| tcx.def_path_str(object.def_id));
+            let label = if Some(*object) == catch_all {
+                "catch-all".to_string()
+            } else if locks.contains(object) {
+                format!("nested transaction\n{}", span)
+            } else {
+                span
+            };
+            (*object, label)
+        })
+        .collect();
+    nodes.sort_by_cached_key(|(object, label)| (label.clone(), format!("{:?}", object)));
+    let index: FxHashMap<UniqueId, usize> =
+        nodes.iter().enumerate().map(|(i, (object, _))| (*object, i)).collect();
+
+    // The edges are only built for the graph: there are O(K|W|^2) of them.
+    let mut functions_of: FxHashMap<(usize, usize), FxHashSet<String>> = Default::default();
+    for (transaction, shared_objects) in &analysis.transactions {
+        let function = tcx.def_path_str(transaction.lock.def_id);
+        for u in shared_objects {
+            for v in shared_objects {
+                // The graph is undirected, so draw every edge once.
+                if index[u] < index[v] {
+                    functions_of.entry((index[u], index[v])).or_default().insert(function.clone());
                 }
             }
         }
     }
+    let mut edges: Vec<(usize, usize, String)> = functions_of
+        .into_iter()
+        .map(|((u, v), functions)| {
+            let mut functions: Vec<String> = functions.into_iter().collect();
+            functions.sort();
+            (u, v, functions.join("\n"))
+        })
+        .collect();
+    edges.sort();
+
+    let clusters = nodes
+        .iter()
+        .map(|(object, _)| {
+            let transaction = analysis.vertices[object].iter().next().unwrap();
+            conflict_set_of.get(transaction).cloned()
+        })
+        .collect();
+    let graph = ConflictGraph {
+        name: tcx.crate_name(LOCAL_CRATE).to_string(),
+        nodes: nodes.into_iter().map(|(_, label)| label).collect(),
+        clusters,
+        edges,
+    };
+
+    let path = tcx.output_filenames(LOCAL_CRATE).with_extension("conflicts.dot");
+    info!("[STM] writing the conflict graph to {}", path.display());
+    let mut dot = vec![];
+    dot::render(&graph, &mut dot).unwrap();
+    if let Err(e) = fs::write(&path, dot) {
+        tcx.sess.err(&format!("failed to write `{}`: {}", path.display(), e));
+    }
+}
+
+/// The conflict graph, as rendered by `dump_conflict_graph`.
+struct ConflictGraph {
+    name: String,
+    /// The label of every node.
+    nodes: Vec<String>,
+    /// The conflict set of every node.
+    clusters: Vec<Option<usize>>,
+    /// Pairs of nodes, with the functions of the transactions that use both.
+    edges: Vec<(usize, usize, String)>,
+}
+
+impl<'a> dot::Labeller<'a> for ConflictGraph {
+    type Node = usize;
+    type Edge = &'a (usize, usize, String);
+
+    fn graph_id(&'a self) -> dot::Id<'a> {
+        let name: String =
+            self.name.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+        dot::Id::new(format!("conflicts_{}", name)).unwrap()
+    }
+    fn node_id(&'a self, n: &usize) -> dot::Id<'a> {
+        dot::Id::new(format!("object{}", n)).unwrap()
+    }
+    fn node_label(&'a self, n: &usize) -> dot::LabelText<'a> {
+        dot::LabelText::LabelStr(Cow::Borrowed(&self.nodes[*n]))
+    }
+    fn node_shape(&'a self, _n: &usize) -> Option<dot::LabelText<'a>> {
+        Some(dot::LabelText::LabelStr(Cow::Borrowed("box")))
+    }
+    fn node_cluster(&'a self, n: &usize) -> Option<dot::Id<'a>> {
+        self.clusters[*n].map(|i| dot::Id::new(format!("set{}", i)).unwrap())
+    }
+    fn cluster_label(&'a self, cluster: &dot::Id<'a>) -> dot::LabelText<'a> {
+        let i = &cluster.as_slice()["set".len()..];
+        dot::LabelText::LabelStr(format!("conflict set {}", i).into())
+    }
+    fn edge_label(&'a self, e: &&'a (usize, usize, String)) -> dot::LabelText<'a> {
+        dot::LabelText::LabelStr(Cow::Borrowed(&e.2))
+    }
+}
+
+impl<'a> dot::GraphWalk<'a> for ConflictGraph {
+    type Node = usize;
+    type Edge = &'a (usize, usize, String);
+
+    fn nodes(&'a self) -> dot::Nodes<'a, usize> {
+        (0..self.nodes.len()).collect()
+    }
+    fn edges(&'a self) -> dot::Edges<'a, &'a (usize, usize, String)> {
+        self.edges.iter().collect()
+    }
+    fn source(&'a self, edge: &&'a (usize, usize, String)) -> usize {
+        edge.0
+    }
+    fn target(&'a self, edge: &&'a (usize, usize, String)) -> usize {
+        edge.1
+    }
 }
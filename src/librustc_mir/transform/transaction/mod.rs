@@ -4,17 +4,31 @@
 //! A pass that identifies shared objects (TxCells) in transactions.
 pub use self::conflict_analysis::ConflictAnalysis;
 pub use self::use_def_analysis::UseDefVisitor;
+use self::conflict_analysis::ObjectKey;
+use self::transaction_map::TransactionMap;
 use crate::util::patch::MirPatch;
-use rustc::hir::def_id::DefId;
-use rustc::hir::{Expr, ExprKind, Item, ItemKind, Node};
+use rustc::hir::def_id::{DefId, LOCAL_CRATE};
+use rustc::hir::BodyOwnerKind;
+use rustc::lint::builtin::EMPTY_TRANSACTIONS;
 use rustc::mir::{
-    BasicBlock, Body, Constant, Local, Operand, Place, TerminatorKind, Transaction, UniqueId,
+    BasicBlock, BasicBlockData, Body, ClearCrossCrate, Constant, Local, LocalDecl, Operand, Place,
+    SourceInfo, Terminator, TerminatorKind, TransactionLock, UniqueId, OUTERMOST_SOURCE_SCOPE,
+    START_BLOCK,
 };
+use rustc::ty::subst::InternalSubsts;
 use rustc::ty::{Const, FnDef, TyCtxt};
 use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::stable_hasher::StableHasher;
+use rustc_data_structures::unify::{InPlace, UnificationTable};
 use std::convert::TryInto;
+use std::hash::Hash;
+use std::iter;
+use syntax_pos::Span;
 
+pub mod blocking;
 pub mod conflict_analysis;
+pub mod report;
+pub mod shared_object;
 pub mod transaction_map;
 pub mod use_def_analysis;
 
@@ -25,15 +39,38 @@ pub fn local_from_dest(destination: &Option<(Place<'tcx>, BasicBlock)>) -> Optio
     }
 }
 
+/// Whether the transaction analysis looks into the body of `def_id`: the functions,
+/// closures and constructors of this crate. Const fns can't run transactions, and
+/// const evaluation may steal their validated MIR at any time, so they are left out.
+pub fn is_analyzed(tcx: TyCtxt<'_>, def_id: DefId) -> bool {
+    if !def_id.is_local() || !tcx.mir_keys(LOCAL_CRATE).contains(&def_id) {
+        return false;
+    }
+    let hir_id = tcx.hir().as_local_hir_id(def_id).unwrap();
+    match tcx.hir().body_owner_kind(hir_id) {
+        BodyOwnerKind::Fn | BodyOwnerKind::Closure => {
+            tcx.is_constructor(def_id) || !tcx.is_const_fn(def_id)
+        }
+        BodyOwnerKind::Const | BodyOwnerKind::Static(_) => false,
+    }
+}
+
+/// Return the span of the call at `id`, if it's in a function of this crate.
+pub fn call_span(tcx: TyCtxt<'_>, id: &UniqueId) -> Option<Span> {
+    if !is_analyzed(tcx, id.def_id) {
+        return None;
+    }
+    let blocks = tcx.transaction_blocks(id.def_id);
+    blocks.call_spans.iter().find(|(location, _)| *location == id.location).map(|&(_, span)| span)
+}
+
 fn transaction_call(tcx: TyCtxt<'tcx>, is_lock: bool, is_write: bool) -> DefId {
-    // We only run when the transaction optimization level is nonzero.
     match tcx.sess.opts.debugging_opts.transaction_level {
-        0 => panic!("transaction optimizations are not supported"),
-        1 => match is_lock {
+        0 | 1 => match is_lock {
             true => tcx.lang_items().transaction_lock().expect("transaction_lock not defined"),
             false => tcx.lang_items().transaction_unlock().expect("transaction_unlock not defined"),
         },
-        2 => {
+        2 | 3 => {
             let lang_items = tcx.lang_items();
             match (is_lock, is_write) {
                 (true, true) => {
@@ -50,7 +87,7 @@ fn transaction_call(tcx: TyCtxt<'tcx>, is_lock: bool, is_write: bool) -> DefId {
                     .expect("transaction_read_unlock not defined"),
             }
         }
-        _ => panic!("unknown transaction optimization level"),
+        level => bug!("invalid transaction level {}", level),
     }
 }
 
@@ -58,7 +95,7 @@ fn patch_call(
     body: &Body<'tcx>,
     fn_id: &UniqueId,
     tcx: TyCtxt<'tcx>,
-    i: usize,
+    id: usize,
     is_lock: bool,
     is_write: bool,
 ) -> TerminatorKind<'tcx> {
@@ -84,7 +121,7 @@ fn patch_call(
             let new_arg = Operand::Constant(box Constant {
                 span: constant.span,
                 user_ty: None,
-                literal: Const::from_usize(tcx, i.try_into().unwrap()),
+                literal: Const::from_usize(tcx, id.try_into().unwrap()),
             });
             *args = vec![new_arg];
         }
@@ -93,37 +130,249 @@ fn patch_call(
     new_term_kind
 }
 
-pub fn make_patches(def_id: DefId, tcx: TyCtxt<'tcx>) -> FxHashMap<DefId, MirPatch<'tcx>> {
-    let mut patches: FxHashMap<DefId, MirPatch<'_>> = Default::default();
+/// Replace a lock or unlock call with a jump to where it returns to.
+fn elide_call(body: &Body<'tcx>, fn_id: &UniqueId) -> TerminatorKind<'tcx> {
+    let term_kind = body[fn_id.location.block].terminator().clone().kind;
+    match term_kind {
+        TerminatorKind::Call { destination: Some((_, target)), .. } => {
+            TerminatorKind::Goto { target }
+        }
+        _ => term_kind,
+    }
+}
 
-    if let Some(hir_id) = tcx.hir().as_local_hir_id(def_id) {
-        match tcx.hir().find(hir_id) {
-            Some(Node::Item(&Item { kind: ItemKind::Fn(..), .. }))
-            | Some(Node::Expr(&Expr { kind: ExprKind::Closure(..), .. })) => {
-                debug!("[STM] function or closure!! {:?} get patches.", def_id);
-                let conflict_sets = tcx.conflict_analysis(def_id.krate);
+/// Warn that the transaction whose lock call is `lock` does nothing a lock is needed for.
+fn warn_empty_transaction(tcx: TyCtxt<'tcx>, body: &Body<'tcx>, lock: &UniqueId) {
+    let source_info = body.source_info(lock.location);
+    if let ClearCrossCrate::Set(ref data) = body.source_scope_local_data {
+        let lint_root = data[source_info.scope].lint_root;
+        let msg = "transaction uses no shared objects";
+        tcx.struct_span_lint_hir(EMPTY_TRANSACTIONS, lint_root, source_info.span, msg)
+            .span_label(source_info.span, "this block takes no lock")
+            .help("remove the `transaction` keyword, or access shared objects inside the block")
+            .emit();
+    }
+}
 
-                for (i, conflict_set) in conflict_sets.iter().enumerate() {
-                    debug!("[STM] conflict set {}: {} transactions", i, conflict_set.len());
-                    for Transaction { lock, unlock, is_write } in conflict_set {
-                        let (body_ref, _) = tcx.mir_validated(lock.def_id);
-                        let body = &body_ref.borrow();
+/// Patch the lock call and the unlock calls of a transaction to pass its ID and take
+/// the right kind of lock, or remove them if the transaction needs no lock.
+fn patch_transaction(
+    patch: &mut MirPatch<'tcx>,
+    body: &Body<'tcx>,
+    tcx: TyCtxt<'tcx>,
+    lock: &UniqueId,
+    unlocks: &[UniqueId],
+    is_write: bool,
+    elide: bool,
+) {
+    let id = transaction_id(tcx, lock) as usize;
+    let calls = iter::once((lock, true)).chain(unlocks.iter().map(|unlock| (unlock, false)));
+    for (call, is_lock) in calls {
+        let new_term_kind = if elide {
+            debug!("[STM] eliding {:?}", call);
+            elide_call(body, call)
+        } else {
+            patch_call(body, call, tcx, id, is_lock, is_write)
+        };
+        patch.patch_terminator(call.location.block, new_term_kind);
+    }
+}
+
+/// Return the ID passed to the lock and unlock calls of the transaction whose lock call
+/// is `lock`. It only depends on where the transaction is, so every crate agrees on it.
+pub fn transaction_id(tcx: TyCtxt<'_>, lock: &UniqueId) -> u32 {
+    let mut hasher = StableHasher::new();
+    tcx.def_path_hash(lock.def_id).hash(&mut hasher);
+    lock.location.block.index().hash(&mut hasher);
+    lock.location.statement_index.hash(&mut hasher);
+    hasher.finish::<u64>() as u32
+}
 
-                        let patch = patches.entry(lock.def_id).or_insert(MirPatch::new(body));
+/// Number the conflict sets of the whole program, and return the lock of every
+/// transaction ID. Lock 0 is left free for the runtime to use for transactions it
+/// doesn't know about. Conflict sets whose transactions share an ID (the same
+/// transaction analyzed in two crates, or a hash collision) share a lock.
+fn lock_table(tcx: TyCtxt<'_>) -> Vec<(u32, usize)> {
+    let conflict_sets = tcx.conflict_analysis(LOCAL_CRATE);
+    let mut table: UnificationTable<InPlace<ObjectKey>> = UnificationTable::new();
+    let keys: Vec<ObjectKey> = conflict_sets.iter().map(|_| table.new_key(())).collect();
+    let mut set_of: FxHashMap<u32, ObjectKey> = Default::default();
+    for (i, conflict_set) in conflict_sets.iter().enumerate() {
+        for transaction in conflict_set {
+            let id = transaction_id(tcx, &transaction.lock);
+            let other = *set_of.entry(id).or_insert(keys[i]);
+            table.union(keys[i], other);
+        }
+    }
 
-                        let new_lock = patch_call(body, lock, tcx, i, true, *is_write);
-                        let new_unlock = patch_call(body, unlock, tcx, i, false, *is_write);
+    let mut locks: FxHashMap<ObjectKey, usize> = Default::default();
+    let mut lock_table: Vec<(u32, usize)> = vec![];
+    for (id, set) in set_of {
+        let root = table.find(set);
+        let next_lock = locks.len() + 1;
+        let lock = *locks.entry(root).or_insert(next_lock);
+        lock_table.push((id, lock));
+    }
+    lock_table.sort();
+    lock_table
+}
 
-                        patch.patch_terminator(lock.location.block, new_lock);
-                        patch.patch_terminator(unlock.location.block, new_unlock);
-                        debug!("[STM] added patches to map");
-                    }
-                }
+/// Register the lock of every transaction in the program with the runtime, at the
+/// start of the program's entry point. The crates a program is made of are compiled
+/// separately, so their lock and unlock calls pass transaction IDs, and the crate
+/// with the entry point, which sees the transactions of every crate, hands out the locks.
+pub fn register_lock_table(tcx: TyCtxt<'tcx>, body: &mut Body<'tcx>) {
+    if tcx.sess.opts.debugging_opts.transaction_level == 0 {
+        return;
+    }
+    let register = match tcx.lang_items().transaction_register() {
+        Some(register) => register,
+        None => return,
+    };
+    let table = lock_table(tcx);
+    debug!("[STM] lock table {:?}", table);
+    if table.is_empty() {
+        return;
+    }
+
+    let span = body.span;
+    let source_info = SourceInfo { span, scope: OUTERMOST_SOURCE_SCOPE };
+    let usize_operand = |n: usize| {
+        Operand::Constant(box Constant {
+            span,
+            user_ty: None,
+            literal: Const::from_usize(tcx, n.try_into().unwrap()),
+        })
+    };
+    let func = Operand::function_handle(tcx, register, InternalSubsts::empty(), span);
+    let dest = body.local_decls.push(LocalDecl::new_temp(tcx.mk_unit(), span));
+
+    // Move the original start block out of the way, so the registration runs only
+    // once even if the body loops back to its start.
+    let start = body[START_BLOCK].clone();
+    let start = body.basic_blocks_mut().push(start);
+    for block_data in body.basic_blocks_mut() {
+        for target in block_data.terminator_mut().successors_mut() {
+            if *target == START_BLOCK {
+                *target = start;
             }
-            Some(other) => debug!("[STM] other defkind {:?}; no patches {:?}", other, def_id),
-            None => debug!("[STM] defkind None; no patches {:?}", def_id),
         }
     }
 
-    patches
+    let mut target = start;
+    for (i, &(id, lock)) in table.iter().enumerate().rev() {
+        let kind = TerminatorKind::Call {
+            func: func.clone(),
+            args: vec![usize_operand(id as usize), usize_operand(lock)],
+            destination: Some((Place::from(dest), target)),
+            cleanup: None,
+            from_hir_call: false,
+        };
+        let block_data = BasicBlockData::new(Some(Terminator { source_info, kind }));
+        if i == 0 {
+            body.basic_blocks_mut()[START_BLOCK] = block_data;
+        } else {
+            target = body.basic_blocks_mut().push(block_data);
+        }
+    }
+}
+
+/// Decide how the transactions of `def_id` are patched, from the conflict sets of
+/// the crate, for the `transaction_locks` query.
+pub fn transaction_locks(tcx: TyCtxt<'_>, def_id: DefId) -> Vec<TransactionLock> {
+    let blocks = tcx.transaction_blocks(def_id);
+    // Functions without transactions don't depend on the conflict sets at all.
+    if blocks.transactions.is_empty() {
+        return vec![];
+    }
+    let level = tcx.sess.opts.debugging_opts.transaction_level;
+    if level == 0 {
+        // Without the analysis, no transaction is registered with the
+        // runtime, so they all take the global lock.
+        return blocks
+            .transactions
+            .iter()
+            .map(|(lock, unlocks)| TransactionLock {
+                lock: *lock,
+                unlocks: unlocks.clone(),
+                conflict_set: None,
+                is_write: true,
+                elide: false,
+            })
+            .collect();
+    }
+
+    let conflict_sets = tcx.conflict_analysis(LOCAL_CRATE);
+    // Only a program sees every transaction that can use its shared objects.
+    let is_program = tcx.entry_fn(LOCAL_CRATE).is_some();
+    // The same transaction can be in a conflict set once as a reader and once as a writer,
+    // so it is a writer, or conflicts with one, if it does in any of its conflict sets.
+    let mut conflict_set_of: FxHashMap<UniqueId, (usize, bool, bool)> = Default::default();
+    for (i, conflict_set) in conflict_sets.iter().enumerate() {
+        let has_writers = conflict_set.iter().any(|transaction| transaction.is_write);
+        for transaction in conflict_set.iter().filter(|t| t.lock.def_id == def_id) {
+            let entry = conflict_set_of.entry(transaction.lock).or_insert((i, false, false));
+            entry.1 |= transaction.is_write;
+            entry.2 |= has_writers;
+        }
+    }
+
+    let mut locks = vec![];
+    for (lock, unlocks) in &blocks.transactions {
+        let transaction_lock = match conflict_set_of.get(lock) {
+            Some(&(i, is_write, has_writers)) => {
+                // Readers don't conflict with each other, so a conflict set without
+                // writers needs no lock at all.
+                let elide = level >= 3 && is_program && !has_writers;
+                debug!("[STM] {:?} is in conflict set {}", lock, i);
+                TransactionLock {
+                    lock: *lock,
+                    unlocks: unlocks.clone(),
+                    conflict_set: Some(i),
+                    is_write,
+                    elide,
+                }
+            }
+            // A transaction that uses no shared objects is in no conflict set.
+            // It can't conflict with anything, so it needs no lock.
+            None => TransactionLock {
+                lock: *lock,
+                unlocks: unlocks.clone(),
+                conflict_set: None,
+                is_write: false,
+                elide: true,
+            },
+        };
+        locks.push(transaction_lock);
+    }
+    locks
+}
+
+/// Patch the lock and unlock calls of the transactions in `body`, the validated MIR of
+/// `def_id`, which must be analyzed.
+pub fn make_patches(def_id: DefId, tcx: TyCtxt<'tcx>, body: &Body<'tcx>) -> MirPatch<'tcx> {
+    let mut patch = MirPatch::new(body);
+
+    // Nested transactions are flattened into the outermost one.
+    for call in &tcx.transaction_blocks(def_id).nested {
+        debug!("[STM] removing nested {:?}", call);
+        patch.patch_terminator(call.location.block, elide_call(body, call));
+    }
+
+    let level = tcx.sess.opts.debugging_opts.transaction_level;
+    for transaction in tcx.transaction_locks(def_id) {
+        if level > 0 && transaction.conflict_set.is_none() {
+            warn_empty_transaction(tcx, body, &transaction.lock);
+        }
+        patch_transaction(
+            &mut patch,
+            body,
+            tcx,
+            &transaction.lock,
+            &transaction.unlocks,
+            transaction.is_write,
+            transaction.elide,
+        );
+    }
+    patch
 }
@@ -0,0 +1,147 @@
+/// Copyright 2021, MASSACHUSETTS INSTITUTE OF TECHNOLOGY
+/// Subject to FAR 52.227-11 – Patent Rights – Ownership by the Contractor (May 2014)
+/// SPDX-License-Identifier: MIT
+//! Write the transactions of a crate, their shared objects and their locks to a
+//! JSON file (`-Z dump-transactions=json`).
+use super::shared_object::catch_all;
+use super::{call_span, is_analyzed, lock_table, transaction_id};
+use rustc::hir::def_id::{DefId, LOCAL_CRATE};
+use rustc::mir::{TransactionUse, UniqueId};
+use rustc::ty::TyCtxt;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_serialize::json;
+use std::fs;
+
+#[derive(RustcEncodable)]
+struct CrateReport {
+    krate: String,
+    transaction_level: usize,
+    transactions: Vec<TransactionReport>,
+}
+
+#[derive(RustcEncodable)]
+struct TransactionReport {
+    /// The ID passed to the lock and unlock calls.
+    id: u32,
+    function: String,
+    span: String,
+    /// `read` or `write`.
+    mode: String,
+    /// The index of the conflict set in the crate's conflict analysis, if the
+    /// transaction uses any shared objects.
+    conflict_set: Option<usize>,
+    /// The lock this crate registers for the transaction, if it takes one.
+    lock: Option<usize>,
+    shared_objects: Vec<SharedObjectReport>,
+}
+
+#[derive(RustcEncodable)]
+struct SharedObjectReport {
+    /// `allocation`, `static`, `catch_all` for the shared objects passed to unknown code,
+    /// or `nested_transaction` for the pseudo shared object of a transaction run from
+    /// inside this one.
+    kind: String,
+    function: String,
+    /// Only known for allocations in this crate, and for statics.
+    span: Option<String>,
+    mode: String,
+}
+
+fn mode(is_write: bool) -> String {
+    if is_write { "write" } else { "read" }.to_string()
+}
+
+/// Return the source span of the call at `id`, if it's in this crate, or of the static
+/// whose shared object it is.
+pub(super) fn span_of(tcx: TyCtxt<'_>, id: &UniqueId) -> Option<String> {
+    let span = match tcx.is_static(id.def_id) {
+        true => Some(tcx.def_span(id.def_id)),
+        false => call_span(tcx, id),
+    };
+    span.map(|span| tcx.sess.source_map().span_to_string(span))
+}
+
+pub fn dump_transactions(tcx: TyCtxt<'_>) {
+    let level = tcx.sess.opts.debugging_opts.transaction_level;
+    let catch_all = catch_all(tcx);
+
+    // The same transaction can be analyzed from several functions, with different
+    // shared objects each time.
+    let mut uses: FxHashMap<UniqueId, FxHashSet<TransactionUse>> = Default::default();
+    for allocation_set in tcx.crate_transactions(LOCAL_CRATE) {
+        uses.entry(allocation_set.lock).or_default().extend(allocation_set.allocations);
+    }
+    let locks: FxHashMap<u32, usize> = if level == 0 {
+        Default::default()
+    } else {
+        lock_table(tcx).into_iter().collect()
+    };
+
+    let mut locals: Vec<DefId> = tcx
+        .mir_keys(LOCAL_CRATE)
+        .iter()
+        .filter(|def_id| is_analyzed(tcx, **def_id))
+        .cloned()
+        .collect();
+    locals.sort_by_key(|def_id| tcx.def_path_str(*def_id));
+
+    let mut transactions = vec![];
+    for def_id in locals {
+        // The transactions as they are patched, with their conflict set and lock mode.
+        for transaction in tcx.transaction_locks(def_id) {
+            let lock = transaction.lock;
+            let id = transaction_id(tcx, &lock);
+            let mut shared_objects: Vec<SharedObjectReport> = uses
+                .get(&lock)
+                .into_iter()
+                .flatten()
+                .map(|tx_use| {
+                    let object = &tx_use.shared_object;
+                    let kind = if Some(*object) == catch_all {
+                        "catch_all"
+                    } else if uses.contains_key(object) {
+                        "nested_transaction"
+                    } else if tcx.is_static(object.def_id) {
+                        "static"
+                    } else {
+                        "allocation"
+                    };
+                    SharedObjectReport {
+                        kind: kind.to_string(),
+                        function: tcx.def_path_str(object.def_id),
+                        span: span_of(tcx, object),
+                        mode: mode(tx_use.is_write),
+                    }
+                })
+                .collect();
+            shared_objects.sort_by(|a, b| (&a.function, &a.span).cmp(&(&b.function, &b.span)));
+
+            // Level 0 registers no locks, so every transaction takes the global lock 0.
+            let lock_index = match level {
+                _ if transaction.elide => None,
+                0 => Some(0),
+                _ => locks.get(&id).cloned(),
+            };
+            transactions.push(TransactionReport {
+                id,
+                function: tcx.def_path_str(def_id),
+                span: span_of(tcx, &lock).unwrap(),
+                mode: mode(transaction.is_write),
+                conflict_set: transaction.conflict_set,
+                lock: lock_index,
+                shared_objects,
+            });
+        }
+    }
+
+    let report = CrateReport {
+        krate: tcx.crate_name(LOCAL_CRATE).to_string(),
+        transaction_level: level,
+        transactions,
+    };
+    let path = tcx.output_filenames(LOCAL_CRATE).with_extension("transactions.json");
+    info!("[STM] writing {} transactions to {}", report.transactions.len(), path.display());
+    if let Err(e) = fs::write(&path, json::as_pretty_json(&report).to_string()) {
+        tcx.sess.err(&format!("failed to write `{}`: {}", path.display(), e));
+    }
+}
@@ -0,0 +1,232 @@
+/// Copyright 2021, MASSACHUSETTS INSTITUTE OF TECHNOLOGY
+/// Subject to FAR 52.227-11 – Patent Rights – Ownership by the Contractor (May 2014)
+/// SPDX-License-Identifier: MIT
+//! Recognize shared objects and the calls that create, move and access them.
+//!
+//! The shared object type is the `tx_ptr` lang item. It is created by the
+//! `tx_ptr_new` lang item and accessed by the `tx_ptr_borrow` (read) and
+//! `tx_ptr_borrow_mut` (write) lang items. Other methods that access the shared
+//! objects they are given are marked `#[rustc_tx_access = "read"]` or
+//! `#[rustc_tx_access = "write"]`, so any crate can define conforming types.
+//!
+//! Containers of shared objects are either seen through (their fields are searched
+//! for a `tx_ptr`) or marked `#[tx_container]`. The methods of a container that
+//! return a stored shared object, store their argument, or return a view into the
+//! container are marked `#[tx_container(get)]`, `#[tx_container(insert)]` and
+//! `#[tx_container(project)]`. A method that calls its closure argument with a reference
+//! to what its receiver holds, such as `LocalKey::with`, is marked `#[tx_container(with)]`.
+//! A method that sends its argument on the channel that is its receiver, such as
+//! `Sender::send`, is marked `#[tx_container(send)]`; what the receivers of the channel
+//! get is marked `#[tx_container(get)]`.
+//!
+//! A static that holds shared objects is a shared object of its own, named after the
+//! static by `static_object`.
+//!
+//! Shared objects passed to a callee that can't be resolved at compile time, such as
+//! a trait method called on a trait object or on a type parameter, or a function
+//! pointer, are said to escape. They are put in a catch-all conflict set together with
+//! every transaction that calls unknown code, so that they are never left unlocked.
+use rustc::hir::def_id::DefId;
+use rustc::mir::{Body, Location, Operand, Place, UniqueId, RETURN_PLACE};
+use rustc::ty::subst::SubstsRef;
+use rustc::ty::{self, Instance, InstanceDef, Ty, TyCtxt};
+use rustc_data_structures::fx::FxHashSet;
+use syntax::attr;
+use syntax::symbol::sym;
+
+/// What a call does with the shared objects it is given or returns.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CallKind {
+    /// Creates a new shared object (or a new container of them).
+    Allocation,
+    /// Returns shared objects that came from its arguments, e.g. `Arc::new`,
+    /// `Clone::clone`, `Deref::deref`, `Index::index` and container getters.
+    Flow,
+    /// Reads the shared object it is given.
+    Read,
+    /// Writes the shared object it is given.
+    Write,
+    /// Stores its arguments in the container that is its receiver. Calls to other crates
+    /// that take shared objects, return none and have no summary are assumed to do so,
+    /// since they may keep them, e.g. `Extend::extend`.
+    Insert,
+    /// Calls the function or closure it is given with a reference to what its receiver
+    /// holds, e.g. `LocalKey::with`.
+    With,
+    /// Sends its argument on the channel that is its receiver, e.g. `Sender::send`.
+    Send,
+    /// Anything else. Local callees are traced through their bodies.
+    Other,
+}
+
+/// Classify a call to `fn_def_id` with `args` whose result is stored in `destination`.
+pub fn classify_call(
+    tcx: TyCtxt<'tcx>,
+    body: &Body<'tcx>,
+    fn_def_id: DefId,
+    args: &[Operand<'tcx>],
+    destination: &Place<'tcx>,
+) -> CallKind {
+    let lang_items = tcx.lang_items();
+    if Some(fn_def_id) == lang_items.tx_ptr_new() {
+        return CallKind::Allocation;
+    }
+    if Some(fn_def_id) == lang_items.tx_ptr_borrow() {
+        return CallKind::Read;
+    }
+    if Some(fn_def_id) == lang_items.tx_ptr_borrow_mut() {
+        return CallKind::Write;
+    }
+    let attrs = tcx.get_attrs(fn_def_id);
+    // Malformed attributes are reported by `check_attr`, and ignored here.
+    match attr::first_attr_value_str_by_name(&attrs, sym::rustc_tx_access) {
+        Some(access) if access == sym::read => return CallKind::Read,
+        Some(access) if access == sym::write => return CallKind::Write,
+        _ => {}
+    }
+    if let Some(kind) = container_method_kind(tcx, fn_def_id) {
+        return kind;
+    }
+
+    // Local callees are traced through their bodies, and foreign ones compiled with a
+    // summary of their shared objects are handled by that summary. For the rest, fall
+    // back to the signature: a function that returns shared objects either passes along
+    // those of its arguments or makes new ones, and one that returns none may store
+    // those of its arguments in its receiver.
+    if fn_def_id.is_local() || !tcx.shared_object_summary(fn_def_id).is_empty() {
+        return CallKind::Other;
+    }
+    let takes_shared_objects =
+        args.iter().any(|arg| contains_shared_object(tcx, arg.ty(body, tcx)));
+    match (contains_shared_object(tcx, destination.ty(body, tcx).ty), takes_shared_objects) {
+        (true, true) => CallKind::Flow,
+        (true, false) => CallKind::Allocation,
+        (false, true) => CallKind::Insert,
+        (false, false) => CallKind::Other,
+    }
+}
+
+/// Return what a method marked `#[tx_container(..)]` does with shared objects.
+fn container_method_kind(tcx: TyCtxt<'_>, fn_def_id: DefId) -> Option<CallKind> {
+    let attrs = tcx.get_attrs(fn_def_id);
+    let attr = attr::find_by_name(&attrs, sym::tx_container)?;
+    let role = match &attr.meta_item_list()?[..] {
+        [role] => role.name_or_empty(),
+        _ => return None,
+    };
+    match role {
+        sym::get | sym::project => Some(CallKind::Flow),
+        sym::insert => Some(CallKind::Insert),
+        sym::send => Some(CallKind::Send),
+        sym::with => Some(CallKind::With),
+        _ => None,
+    }
+}
+
+/// Resolve a call to `fn_def_id` with `substs` to the function that is actually run,
+/// e.g. the impl of a trait method. Return `None` if that isn't known until runtime,
+/// because it's a virtual call, a call through a function pointer, or a trait method
+/// whose impl depends on the type parameters of the caller. Opaque types are revealed,
+/// so calling a closure returned as an `impl Fn` runs that closure.
+pub fn resolve_callee(
+    tcx: TyCtxt<'tcx>,
+    param_env: ty::ParamEnv<'tcx>,
+    fn_def_id: DefId,
+    substs: SubstsRef<'tcx>,
+) -> Option<DefId> {
+    let param_env = param_env.with_reveal_all();
+    let substs = tcx.normalize_erasing_regions(param_env, substs);
+    let instance = Instance::resolve(tcx, param_env, fn_def_id, substs)?;
+    match instance.def {
+        InstanceDef::Item(def_id) | InstanceDef::Intrinsic(def_id) => Some(def_id),
+        InstanceDef::Virtual(..) | InstanceDef::FnPtrShim(..) => None,
+        _ => Some(instance.def_id()),
+    }
+}
+
+/// The pseudo shared object that stands for every escaped shared object. It is named
+/// after the `tx_ptr` lang item so that it is the same in every crate.
+pub fn catch_all(tcx: TyCtxt<'_>) -> Option<UniqueId> {
+    tcx.lang_items().tx_ptr().map(|def_id| UniqueId {
+        def_id,
+        local: RETURN_PLACE,
+        location: Location::START,
+        field: None,
+    })
+}
+
+/// The shared object that stands for the shared objects held by the static `def_id`. It
+/// is named after the static so that it is the same in every crate.
+pub fn static_object(def_id: DefId) -> UniqueId {
+    UniqueId { def_id, local: RETURN_PLACE, location: Location::START, field: None }
+}
+
+/// Check whether a value of type `ty` might hold a shared object, assuming the worst
+/// of type parameters, trait objects and opaque types.
+pub fn may_contain_shared_object(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> bool {
+    contains_shared_object(tcx, ty)
+        || ty.walk().any(|component| match component.kind {
+            ty::Param(_) | ty::Dynamic(..) | ty::Opaque(..) | ty::Projection(_) => true,
+            _ => false,
+        })
+}
+
+/// Check whether a value of type `ty` can hold a shared object, either directly
+/// or through its type arguments and fields.
+pub fn contains_shared_object(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> bool {
+    match tcx.lang_items().tx_ptr() {
+        Some(tx_ptr) => contains_adt(tcx, ty, tx_ptr, &mut FxHashSet::default()),
+        None => false,
+    }
+}
+
+/// Return the fields of `ty`, or of what it refers to, that can hold shared objects, if
+/// it is a tuple or a struct of the local crate. Other types are only traced as a whole.
+pub fn shared_object_fields(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Vec<usize> {
+    let mut ty = ty;
+    while let ty::Ref(_, referent, _) = ty.kind {
+        ty = referent;
+    }
+    let field_tys: Vec<Ty<'tcx>> = match ty.kind {
+        ty::Tuple(_) => ty.tuple_fields().collect(),
+        ty::Adt(adt_def, substs)
+            if adt_def.is_struct()
+                && adt_def.did.is_local()
+                && !tcx.has_attr(adt_def.did, sym::tx_container) =>
+        {
+            adt_def.non_enum_variant().fields.iter().map(|field| field.ty(tcx, substs)).collect()
+        }
+        _ => vec![],
+    };
+    field_tys
+        .into_iter()
+        .enumerate()
+        .filter(|(_, field_ty)| contains_shared_object(tcx, *field_ty))
+        .map(|(field, _)| field)
+        .collect()
+}
+
+fn contains_adt(
+    tcx: TyCtxt<'tcx>,
+    ty: Ty<'tcx>,
+    adt_did: DefId,
+    visited: &mut FxHashSet<Ty<'tcx>>,
+) -> bool {
+    for component in ty.walk() {
+        if !visited.insert(component) {
+            continue;
+        }
+        if let ty::Adt(adt_def, substs) = component.kind {
+            if adt_def.did == adt_did || tcx.has_attr(adt_def.did, sym::tx_container) {
+                return true;
+            }
+            // Containers may keep their shared objects in private fields.
+            for field in adt_def.all_fields() {
+                if contains_adt(tcx, field.ty(tcx, substs), adt_did, visited) {
+                    return true;
+                }
+            }
+        }
+    }
+    false
+}
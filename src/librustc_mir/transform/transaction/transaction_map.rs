@@ -1,12 +1,13 @@
 /// Copyright 2021, MASSACHUSETTS INSTITUTE OF TECHNOLOGY
 /// Subject to FAR 52.227-11 – Patent Rights – Ownership by the Contractor (May 2014)
 /// SPDX-License-Identifier: MIT
-use crate::transform::transaction::local_from_dest;
+use crate::transform::transaction::{is_analyzed, local_from_dest};
+use crate::transform::transaction::shared_object::resolve_callee;
 use rustc::hir::def_id::DefId;
 use rustc::mir::visit::Visitor;
 use rustc::mir::*;
 use rustc::ty::{FnDef, TyCtxt};
-use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
 
 /// Mark every terminator within this DefId as part or not part of a transaction.
 #[derive(Clone)]
@@ -17,42 +18,31 @@ pub struct TransactionMap<'a, 'tcx> {
     unlock_def_id: Option<DefId>,
     /// Map from a terminator ID to the ID of the lock call of the transaction in which it's contained.
     pub terminator_to_lock: FxHashMap<UniqueId, UniqueId>,
-    /// Map from a transaction's lock ID to its unlock ID.
-    pub lock_to_unlock: FxHashMap<UniqueId, UniqueId>,
+    /// Map from a transaction's lock ID to the IDs of its unlocks, one for every
+    /// way out of the transaction block (normal exit, early exits and unwinding).
+    /// The first unlock identifies the transaction.
+    pub lock_to_unlocks: FxHashMap<UniqueId, Vec<UniqueId>>,
     /// Map from a terminator ID to the lock and unlock ID of the transaction in which it's contained.
     pub terminator_to_tx: FxHashMap<UniqueId, (UniqueId, UniqueId)>,
-    transaction_id: Option<UniqueId>,
+    /// The lock calls in this body, with the blocks they are in.
+    locks: Vec<(UniqueId, BasicBlock)>,
+    /// The lock and unlock calls of the transactions nested in another transaction of
+    /// this body. They are flattened into the outermost transaction, so these calls
+    /// are removed.
+    pub nested: Vec<UniqueId>,
 }
 
 impl<'tcx> Visitor<'tcx> for TransactionMap<'_, 'tcx> {
     /// Visit every terminator in the body. We only need to visit terminators because
     /// function calls are always terminators.
     fn visit_terminator(&mut self, term: &Terminator<'tcx>, location: Location) {
-        if let TerminatorKind::Call { func, destination, .. } = &term.kind {
-            if let Operand::Constant(ref constant) = func {
-                let ty_kind = &constant.literal.ty.kind;
-                if let FnDef(fn_def_id, _substs) = ty_kind {
-                    if *fn_def_id == self.lock_def_id.unwrap() {
-                        let func_local = local_from_dest(destination).unwrap();
-                        let func_id = self.unique_id(&func_local, &location);
-                        debug!("[STM] LOCK: we are in transaction {:?}", func_id);
-                        self.transaction_id = Some(func_id);
-                    } else if *fn_def_id == self.unlock_def_id.unwrap() {
-                        if let Some(lock_id) = &self.transaction_id {
-                            debug!("[STM] UNLOCK: we are no longer in transaction {:?}", lock_id);
-                            let func_local = local_from_dest(destination).unwrap();
-                            let unlock_id = self.unique_id(&func_local, &location);
-                            self.lock_to_unlock.insert(lock_id.clone(), unlock_id);
-                            self.transaction_id = None;
-                        } else {
-                            warn!("[STM] double unlock!");
-                        }
-                    } else if let Some(tx_id) = &self.transaction_id {
-                        if let Some(func_local) = local_from_dest(destination) {
-                            let func_id = self.unique_id(&func_local, &location);
-                            self.terminator_to_lock.insert(func_id, tx_id.clone());
-                        }
-                    }
+        if let TerminatorKind::Call { destination, .. } = &term.kind {
+            if let Some(fn_def_id) = Self::callee(term) {
+                if Some(fn_def_id) == self.lock_def_id {
+                    let func_local = local_from_dest(destination).unwrap();
+                    let lock_id = self.unique_id(&func_local, &location);
+                    debug!("[STM] LOCK: found transaction {:?}", lock_id);
+                    self.locks.push((lock_id, location.block));
                 }
             }
         }
@@ -60,38 +50,6 @@ impl<'tcx> Visitor<'tcx> for TransactionMap<'_, 'tcx> {
 }
 
 impl<'a, 'tcx> TransactionMap<'_, 'tcx> {
-    /// Create a new TransactionMap based on an existing one.
-    pub fn new_child(
-        def_id: DefId,
-        body: &'a Body<'tcx>,
-        transaction_ids: Option<(UniqueId, UniqueId)>,
-        tcx: TyCtxt<'tcx>,
-        terminator_to_tx: FxHashMap<UniqueId, (UniqueId, UniqueId)>,
-    ) -> TransactionMap<'a, 'tcx> {
-        let lock_def_id = tcx.lang_items().transaction_lock();
-        let unlock_def_id = tcx.lang_items().transaction_unlock();
-
-        let mut lock_to_unlock = FxHashMap::default();
-        let transaction_id = match transaction_ids {
-            None => None,
-            Some((lock_id, unlock_id)) => {
-                lock_to_unlock.insert(lock_id, unlock_id);
-                Some(lock_id)
-            }
-        };
-
-        TransactionMap {
-            body,
-            def_id,
-            lock_def_id,
-            unlock_def_id,
-            terminator_to_lock: FxHashMap::default(),
-            lock_to_unlock,
-            terminator_to_tx,
-            transaction_id,
-        }
-    }
-
     /// Create a new TransactionMap.
     pub fn new(def_id: DefId, body: &'a Body<'tcx>, tcx: TyCtxt<'tcx>) -> TransactionMap<'a, 'tcx> {
         let lock_def_id = tcx.lang_items().transaction_lock();
@@ -102,9 +60,10 @@ impl<'a, 'tcx> TransactionMap<'_, 'tcx> {
             lock_def_id,
             unlock_def_id,
             terminator_to_lock: FxHashMap::default(),
-            lock_to_unlock: FxHashMap::default(),
+            lock_to_unlocks: FxHashMap::default(),
             terminator_to_tx: FxHashMap::default(),
-            transaction_id: None,
+            locks: vec![],
+            nested: vec![],
         }
     }
 
@@ -113,11 +72,133 @@ impl<'a, 'tcx> TransactionMap<'_, 'tcx> {
             for (block, block_data) in traversal::reverse_postorder(self.body) {
                 self.visit_basic_block_data(block, block_data);
             }
+            // Outer locks come before the locks nested in them in reverse postorder.
+            for (lock_id, block) in self.locks.clone() {
+                if !self.nested.contains(&lock_id) {
+                    self.visit_transaction(lock_id, block);
+                }
+            }
         }
         for (term, lock) in self.terminator_to_lock.iter() {
-            let unlock = self.lock_to_unlock.get(lock).unwrap();
-            self.terminator_to_tx.insert(*term, (*lock, *unlock));
+            if let Some(unlocks) = self.lock_to_unlocks.get(lock) {
+                self.terminator_to_tx.insert(*term, (*lock, unlocks[0]));
+            }
+        }
+    }
+
+    /// Walk the CFG from the lock call in `lock_block` until every path reaches
+    /// an unlock call, marking the calls on the way as inside the transaction.
+    /// Unwind edges are followed too, so the unlocks on cleanup paths are found.
+    /// Transactions nested in this one are flattened into it: the calls inside them
+    /// are marked as inside this transaction, and their lock and unlock calls as nested.
+    fn visit_transaction(&mut self, lock_id: UniqueId, lock_block: BasicBlock) {
+        let body = self.body;
+        let mut unlocks = vec![];
+        // The nesting depth of every block, relative to this transaction.
+        let mut visited: FxHashSet<(BasicBlock, usize)> = FxHashSet::default();
+        let mut worklist: Vec<(BasicBlock, usize)> =
+            body[lock_block].terminator().successors().map(|&block| (block, 0)).collect();
+
+        while let Some((block, mut depth)) = worklist.pop() {
+            if !visited.insert((block, depth)) {
+                continue;
+            }
+            let term = body[block].terminator();
+            let location = body.terminator_loc(block);
+            match term.kind {
+                TerminatorKind::Call { ref destination, .. } => {
+                    let callee = Self::callee(term);
+                    if let Some(func_local) = local_from_dest(destination) {
+                        let func_id = self.unique_id(&func_local, &location);
+                        if callee == self.unlock_def_id {
+                            if depth == 0 {
+                                debug!("[STM] UNLOCK: {:?} ends tx {:?}", func_id, lock_id);
+                                unlocks.push(func_id);
+                                continue;
+                            }
+                            debug!("[STM] {:?} ends a tx nested in {:?}", func_id, lock_id);
+                            self.nested.push(func_id);
+                            depth -= 1;
+                        } else if callee == self.lock_def_id {
+                            debug!("[STM] flattening transaction {:?} into {:?}", func_id, lock_id);
+                            self.nested.push(func_id);
+                            depth += 1;
+                        } else {
+                            self.terminator_to_lock.insert(func_id, lock_id);
+                        }
+                    }
+                }
+                TerminatorKind::Return | TerminatorKind::Resume => {
+                    warn!("[STM] transaction {:?} can exit without unlocking", lock_id);
+                }
+                _ => {}
+            }
+            worklist.extend(term.successors().map(|&block| (block, depth)));
+        }
+
+        if unlocks.is_empty() {
+            warn!("[STM] transaction {:?} is never unlocked", lock_id);
+            return;
+        }
+        // Identify the transaction by an unlock on a non-cleanup path.
+        unlocks.sort_by_key(|unlock| (body[unlock.location.block].is_cleanup, unlock.location));
+        self.lock_to_unlocks.insert(lock_id, unlocks);
+    }
+
+    /// Collect the outermost transactions of `body` and the calls it makes, for the
+    /// `transaction_blocks` query.
+    pub fn blocks(def_id: DefId, body: &Body<'tcx>, tcx: TyCtxt<'tcx>) -> TransactionBlocks {
+        let mut transaction_map = TransactionMap::new(def_id, body, tcx);
+        transaction_map.perform();
+        let mut transactions: Vec<_> = transaction_map.lock_to_unlocks.into_iter().collect();
+        transactions.sort_by_key(|(lock, _)| lock.location);
+
+        let param_env = tcx.param_env(def_id);
+        let mut callees = vec![];
+        let mut call_spans = vec![];
+        for (block, block_data) in body.basic_blocks().iter_enumerated() {
+            let terminator = block_data.terminator();
+            if let TerminatorKind::Call { func, .. } = &terminator.kind {
+                if let FnDef(callee, substs) = func.ty(body, tcx).kind {
+                    callees.extend(resolve_callee(tcx, param_env, callee, substs));
+                }
+                call_spans.push((body.terminator_loc(block), terminator.source_info.span));
+            }
+        }
+        callees.sort();
+        callees.dedup();
+        TransactionBlocks { transactions, nested: transaction_map.nested, callees, call_spans }
+    }
+
+    /// Return the outermost transactions run by a call to `def_id`, in its body or in the
+    /// bodies of the local functions it calls, as (lock ID, unlock ID) pairs. Callees that
+    /// can't be resolved, or whose MIR is in another crate, are not searched.
+    pub fn transactions_run_by(tcx: TyCtxt<'tcx>, def_id: DefId) -> Vec<(UniqueId, UniqueId)> {
+        let mut transactions = vec![];
+        let mut visited: FxHashSet<DefId> = FxHashSet::default();
+        let mut worklist = vec![def_id];
+        while let Some(def_id) = worklist.pop() {
+            if !visited.insert(def_id) || !is_analyzed(tcx, def_id) {
+                continue;
+            }
+            let blocks = tcx.transaction_blocks(def_id);
+            transactions
+                .extend(blocks.transactions.iter().map(|(lock, unlocks)| (*lock, unlocks[0])));
+            // Calls inside these transactions are flattened into them, but calls outside
+            // them are still inside the caller's transaction.
+            worklist.extend(blocks.callees);
+        }
+        transactions
+    }
+
+    /// Return the DefId of the function called by this terminator, if it is a direct call.
+    fn callee(term: &Terminator<'tcx>) -> Option<DefId> {
+        if let TerminatorKind::Call { func: Operand::Constant(ref constant), .. } = term.kind {
+            if let FnDef(fn_def_id, _substs) = constant.literal.ty.kind {
+                return Some(fn_def_id);
+            }
         }
+        None
     }
 
     /// Create a globally unique ID for a Local.
@@ -1,38 +1,67 @@
 /// Copyright 2021, MASSACHUSETTS INSTITUTE OF TECHNOLOGY
 /// Subject to FAR 52.227-11 – Patent Rights – Ownership by the Contractor (May 2014)
 /// SPDX-License-Identifier: MIT
-use crate::transform::transaction::local_from_dest;
+use crate::transform::transaction::{is_analyzed, local_from_dest};
+use crate::transform::transaction::shared_object::{
+    catch_all, classify_call, contains_shared_object, may_contain_shared_object, resolve_callee,
+    shared_object_fields, static_object, CallKind,
+};
 use crate::transform::transaction::transaction_map::TransactionMap;
 use crate::util::def_use::{DefUseAnalysis, Use};
-use rustc::hir::def_id::{DefId, LOCAL_CRATE};
-use rustc::mir::visit::{PlaceContext, Visitor};
+use rustc::hir::def_id::DefId;
+use rustc::lint::builtin::UNTRANSACTED_ACCESS;
+use rustc::mir::interpret::{ConstValue, GlobalAlloc, Scalar};
+use rustc::mir::visit::Visitor;
 use rustc::mir::*;
-use rustc::ty::subst::GenericArgKind;
-use rustc::ty::{Closure, FnDef, TyCtxt};
+use rustc::ty::subst::{GenericArgKind, SubstsRef};
+use rustc::ty::{Closure, FnDef, Ref, TyCtxt};
 use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_errors::DiagnosticId;
+use std::{cmp, iter, slice};
 
 enum UseKind<'tcx> {
     /// Used in a function with the given DefId as argument # usize.
     Function(Local, DefId, usize),
-    /// Used by another Local.
-    Local(Local),
+    /// Used by another Local, only in the given field of it if there is one.
+    Local(Local, Option<usize>),
     /// Used in a closure with the given arguments.
     ClosureArg(Local, Vec<Operand<'tcx>>),
     /// Used in a final read.
     Read(Local),
     /// Used in a final write.
     Write(Local),
+    /// Passed to a call whose callee isn't known at compile time.
+    Escape(Local),
+    /// Used as the channel a call sends a value on.
+    Sender(Local),
+    /// Sent on a channel by a call, which hands it back if it can't be sent.
+    Sent(Local),
+}
+
+/// A shared object allocated by a call, returned by the callee, which allocated it or
+/// got it from one of its own callees, or held by a static.
+struct Allocation {
+    shared_object: UniqueId,
+    /// Where the uses of the shared object are traced from: the result of the call, or
+    /// the local a static is read or borrowed into.
+    root: UniqueId,
+    /// The number of calls in the call string that identifies `shared_object`.
+    depth: usize,
+    /// What the callee did with the shared object before returning it.
+    returned: Option<ReturnedAllocation>,
 }
 
 /// Find all uses of TxCells/TxPtrs and associate them with a set of unique
 /// TxCell/TxPtr allocations.
 pub struct UseDefVisitor<'a, 'tcx> {
     analysis: DefUseAnalysis,
-    /// The ID of the relevant argument to trace, if relevant
-    arg_id: Option<UniqueId>,
-    /// The index of the relevant argument to trace, if relevant
-    arg_index: Option<usize>,
+    /// The summaries of the functions being summarized together with this one, because
+    /// they call each other.
+    assumed: FxHashMap<DefId, SharedObjectSummary>,
     body: &'a Body<'tcx>,
+    /// When summarizing, whether each access outside of the function's own transactions,
+    /// which happens in the caller's transaction, is a write.
+    caller_accesses: Option<FxHashSet<bool>>,
     /// Mapping from a transaction ID to the set of shared objects it uses.
     pub allocation_set: FxHashMap<(UniqueId, UniqueId), FxHashSet<TransactionUse>>,
     /// The current allocation whose uses we are following.
@@ -40,8 +69,12 @@ pub struct UseDefVisitor<'a, 'tcx> {
     def_id: DefId,
     // Map from a local to all the places it's used.
     pub edges: FxHashMap<UniqueId, FxHashSet<UniqueId>>,
+    /// The allocations that escape into calls whose callee is unknown.
+    escaped: FxHashSet<UniqueId>,
     // Whether the transaction use is a write or read
     is_write: FxHashMap<UniqueId, bool>,
+    /// The calls that send a value on a channel.
+    sends: FxHashSet<UniqueId>,
     tcx: TyCtxt<'tcx>,
     /// Map from a terminator ID to the ID of the transaction in which it's contained.
     pub transaction_map: TransactionMap<'a, 'tcx>,
@@ -52,82 +85,19 @@ impl<'tcx> Visitor<'tcx> for UseDefVisitor<'_, 'tcx> {
     /// Visit every terminator in the body. We only need to visit terminators because
     /// function calls are always terminators.
     fn visit_terminator(&mut self, term: &Terminator<'tcx>, location: Location) {
-        if let TerminatorKind::Call { func, destination, .. } = &term.kind {
-            // TODO: generalize these cases
-            let func_name = format!("{:?}", func);
-            if !(Self::is_new(&func_name)
-                || Self::is_vec(&func_name)
-                || Self::is_tree(&func_name)
-                || Self::is_arc_vec(&func_name))
-            {
-                return;
-            }
-            debug!("[STM] new func {:?}!", func);
-            let func_local = local_from_dest(destination).unwrap();
-            let func_id = self.unique_id(&func_local, &location, None);
-            self.current_allocation = Some(func_id);
-            self.vertices.insert(func_id.clone());
-            self.trace(func_id);
-            self.current_allocation = None;
-        }
+        let allocations = self.allocations(term, location);
+        self.trace_allocations(&allocations);
     }
 
-    fn visit_local(&mut self, &local: &Local, _context: PlaceContext, location: Location) {
-        if self.body.local_kind(local) != LocalKind::Arg || self.arg_id.is_none() {
-            return;
-        }
-        if self.arg_index.unwrap() != local.index() - 1 {
-            return;
-        }
-        debug!("[STM] local {:?} is our argument", local);
-        let old_arg_id = self.arg_id.unwrap();
-        let new_arg_id = match old_arg_id.field {
-            Some(field) => {
-                debug!("[STM] old arg has field, so new arg needs to go to a field.");
-                self.unique_id(&local, &location, Some(field))
-            }
-            None => {
-                debug!("[STM] old arg did not have a field. this is a regular fn call.");
-                self.unique_id(&local, &location, None)
-            }
-        };
-        self.connect(old_arg_id, new_arg_id);
-        debug!("[STM] new edge thru closure or function call {:?} -> {:?}", old_arg_id, new_arg_id);
-        debug!("[STM] recursing into local argument {:?}", new_arg_id);
-        self.vertices.insert(new_arg_id.clone());
-        self.trace(new_arg_id);
+    /// Also visit the reads and borrows of statics, which hold shared objects that
+    /// weren't allocated by any call of the body.
+    fn visit_assign(&mut self, place: &Place<'tcx>, rvalue: &Rvalue<'tcx>, location: Location) {
+        let allocations = self.static_allocations(place, rvalue, location);
+        self.trace_allocations(&allocations);
     }
 }
 
 impl<'a, 'tcx> UseDefVisitor<'_, 'tcx> {
-    /// Create a new UseDefVisitor based on an existing graph.
-    fn new_child(
-        arg_id: Option<UniqueId>,
-        arg_index: Option<usize>,
-        body: &'a Body<'tcx>,
-        def_id: DefId,
-        transaction_map: TransactionMap<'a, 'tcx>,
-        parent: &Self,
-    ) -> UseDefVisitor<'a, 'tcx> {
-        let mut analysis = DefUseAnalysis::new(body);
-        analysis.analyze(body);
-
-        UseDefVisitor {
-            analysis,
-            arg_id,
-            arg_index,
-            body,
-            def_id,
-            allocation_set: parent.allocation_set.clone(),
-            current_allocation: parent.current_allocation.clone(),
-            edges: parent.edges.clone(),
-            is_write: parent.is_write.clone(),
-            tcx: parent.tcx,
-            transaction_map,
-            vertices: parent.vertices.clone(),
-        }
-    }
-
     /// Create a new UseDefVisitor.
     pub fn new(body: &'a Body<'tcx>, def_id: DefId, tcx: TyCtxt<'tcx>) -> UseDefVisitor<'a, 'tcx> {
         let transaction_map = TransactionMap::new(def_id, body, tcx);
@@ -135,26 +105,405 @@ impl<'a, 'tcx> UseDefVisitor<'_, 'tcx> {
         analysis.analyze(body);
         UseDefVisitor {
             analysis,
-            arg_id: None,
-            arg_index: None,
+            assumed: FxHashMap::default(),
             body,
+            caller_accesses: None,
             allocation_set: FxHashMap::default(),
             current_allocation: None,
             def_id,
             edges: FxHashMap::default(),
+            escaped: FxHashSet::default(),
             is_write: FxHashMap::default(),
+            sends: FxHashSet::default(),
             tcx,
             transaction_map,
             vertices: FxHashSet::default(),
         }
     }
 
+    /// Summarize what the function does with the shared objects passed as its arguments,
+    /// and with those it allocates and may return. Each argument, each variable captured
+    /// by a closure and each field of a struct or tuple argument is traced as if it were
+    /// an allocation. The functions in `assumed` are summarized together with this one,
+    /// and their summaries so far are used at the calls to them.
+    pub fn summarize(
+        body: &'a Body<'tcx>,
+        def_id: DefId,
+        tcx: TyCtxt<'tcx>,
+        assumed: &FxHashMap<DefId, SharedObjectSummary>,
+    ) -> SharedObjectSummary {
+        let mut summary = SharedObjectSummary::default();
+        for (index, arg) in body.args_iter().enumerate() {
+            let ty = body.local_decls[arg].ty;
+            let (fields, by_field) = match tcx.type_of(def_id).kind {
+                // The environment of a closure holds the variables it captures.
+                Closure(closure_def_id, substs) if index == 0 => {
+                    let fields = substs
+                        .as_closure()
+                        .upvar_tys(closure_def_id, tcx)
+                        .enumerate()
+                        .filter(|(_, ty)| contains_shared_object(tcx, *ty))
+                        .map(|(field, _)| Some(field))
+                        .collect();
+                    (fields, false)
+                }
+                // Structs and tuples are also summarized field by field, for callers
+                // that know which field holds the shared objects.
+                _ if contains_shared_object(tcx, ty) => {
+                    let by_field = shared_object_fields(tcx, ty);
+                    let fields = iter::once(None).chain(by_field.iter().cloned().map(Some));
+                    (fields.collect(), !by_field.is_empty())
+                }
+                _ => (vec![], false),
+            };
+            for field in fields {
+                let arg_id = UniqueId { def_id, local: arg, location: Location::START, field };
+                let mut visitor = UseDefVisitor::new(body, def_id, tcx);
+                visitor.assumed = assumed.clone();
+                visitor.caller_accesses = Some(FxHashSet::default());
+                visitor.transaction_map.perform();
+                visitor.current_allocation = Some(arg_id);
+                visitor.vertices.insert(arg_id);
+                visitor.trace(arg_id);
+
+                let accesses = visitor.caller_accesses.unwrap();
+                let access = if accesses.contains(&true) {
+                    Some(true)
+                } else if accesses.contains(&false) {
+                    Some(false)
+                } else {
+                    None
+                };
+                let arg_summary = ArgSummary {
+                    index,
+                    field,
+                    access,
+                    escapes: visitor.escaped.contains(&arg_id),
+                    returned: visitor.returns(),
+                    transactions: visitor.transaction_uses(arg_id),
+                };
+                // Callers fall back to the whole argument for fields without a summary.
+                if !arg_summary.is_empty() || (by_field && field.is_some()) {
+                    summary.args.push(arg_summary);
+                }
+            }
+        }
+
+        // The shared objects allocated here or returned by callees, each traced on its own
+        // from every call that allocates or returns it.
+        let mut visitor = UseDefVisitor::new(body, def_id, tcx);
+        visitor.assumed = assumed.clone();
+        let mut allocations = vec![];
+        for (block, data) in body.basic_blocks().iter_enumerated() {
+            for (statement_index, statement) in data.statements.iter().enumerate() {
+                if let StatementKind::Assign(box (ref place, ref rvalue)) = statement.kind {
+                    let location = Location { block, statement_index };
+                    allocations.extend(visitor.static_allocations(place, rvalue, location));
+                }
+            }
+            allocations.extend(visitor.allocations(data.terminator(), body.terminator_loc(block)));
+        }
+        let mut shared_objects: Vec<UniqueId> =
+            allocations.iter().map(|allocation| allocation.shared_object).collect();
+        shared_objects.sort();
+        shared_objects.dedup();
+        for shared_object in shared_objects {
+            let mut visitor = UseDefVisitor::new(body, def_id, tcx);
+            visitor.assumed = assumed.clone();
+            visitor.caller_accesses = Some(FxHashSet::default());
+            visitor.transaction_map.perform();
+            let mut depth = 0;
+            for allocation in allocations.iter().filter(|a| a.shared_object == shared_object) {
+                depth = cmp::max(depth, allocation.depth);
+                visitor.trace_allocations(slice::from_ref(allocation));
+            }
+            if visitor.returns() {
+                summary.allocations.push(ReturnedAllocation {
+                    shared_object,
+                    depth,
+                    escapes: visitor.escaped.contains(&shared_object),
+                    transactions: visitor.transaction_uses(shared_object),
+                });
+            }
+        }
+        summary
+    }
+
+    /// Check whether the traced shared objects may be returned.
+    fn returns(&self) -> bool {
+        self.vertices.iter().any(|v| v.def_id == self.def_id && v.local == RETURN_PLACE)
+    }
+
+    /// Return the uses of `shared_object` in transactions, sorted, since summaries are
+    /// compared to find the fixpoint of recursive functions.
+    fn transaction_uses(&self, shared_object: UniqueId) -> Vec<SummarizedTransactionUse> {
+        let mut transactions = vec![];
+        for ((lock, unlock), uses) in &self.allocation_set {
+            for transaction_use in uses {
+                if transaction_use.shared_object == shared_object {
+                    transactions.push(SummarizedTransactionUse {
+                        lock: *lock,
+                        unlock: *unlock,
+                        is_write: transaction_use.is_write,
+                    });
+                }
+            }
+        }
+        transactions.sort();
+        transactions
+    }
+
+    /// Return the shared objects that the call `term` allocates, or gets from its callee
+    /// or from the functions and closures passed to it.
+    fn allocations(&self, term: &Terminator<'tcx>, location: Location) -> Vec<Allocation> {
+        let (func, args, destination) = match &term.kind {
+            TerminatorKind::Call { func, args, destination, .. } => (func, args, destination),
+            _ => return vec![],
+        };
+        let (fn_def_id, substs, dest) = match (&func.ty(self.body, self.tcx).kind, destination) {
+            (FnDef(fn_def_id, substs), Some((dest, _))) => (*fn_def_id, *substs, dest),
+            _ => return vec![],
+        };
+        let call_id = match local_from_dest(destination) {
+            Some(call_local) => self.unique_id(&call_local, &location, None),
+            None => return vec![],
+        };
+        let mut allocations = vec![];
+        match classify_call(self.tcx, self.body, fn_def_id, args, dest) {
+            CallKind::Allocation => {
+                debug!("[STM] new allocation {:?}!", call_id);
+                let allocation =
+                    Allocation { shared_object: call_id, root: call_id, depth: 0, returned: None };
+                allocations.push(allocation);
+            }
+            CallKind::Other => {
+                allocations.extend(self.returned_allocations(fn_def_id, substs, call_id, true));
+            }
+            _ => {}
+        }
+        // A function or closure passed to the call, such as the initializer of a lazily
+        // initialized static, may return shared objects that the call stores and returns.
+        for arg in args {
+            match arg.ty(self.body, self.tcx).kind {
+                FnDef(def_id, substs) | Closure(def_id, substs) => {
+                    allocations.extend(self.returned_allocations(def_id, substs, call_id, false));
+                }
+                _ => {}
+            }
+        }
+        allocations
+    }
+
+    /// Return the shared objects that a call to `fn_def_id` returns at `call_id`.
+    /// With `-Z allocation-context=k`, those returned by the callee itself (`contextual`)
+    /// are told apart by the last `k` calls that returned them: each is named after the
+    /// call that returned it and its index among those of the callee, until its call
+    /// string is `k` calls long. Otherwise, and for statics, they keep the name the
+    /// callee gave them.
+    fn returned_allocations(
+        &self,
+        fn_def_id: DefId,
+        substs: SubstsRef<'tcx>,
+        call_id: UniqueId,
+        contextual: bool,
+    ) -> Vec<Allocation> {
+        let callee = if self.tcx.is_closure(fn_def_id) {
+            fn_def_id
+        } else {
+            let param_env = self.tcx.param_env(self.def_id);
+            match resolve_callee(self.tcx, param_env, fn_def_id, substs) {
+                Some(callee) => callee,
+                None => return vec![],
+            }
+        };
+        if callee.is_local() && !is_analyzed(self.tcx, callee) {
+            return vec![];
+        }
+        let summary = match self.assumed.get(&callee) {
+            Some(summary) => summary.clone(),
+            None => self.tcx.shared_object_summary(callee),
+        };
+        let context = self.tcx.sess.opts.debugging_opts.allocation_context;
+        summary
+            .allocations
+            .into_iter()
+            .enumerate()
+            .map(|(index, returned)| {
+                let (shared_object, depth) = if contextual
+                    && returned.depth < context
+                    && !self.tcx.is_static(returned.shared_object.def_id)
+                {
+                    (UniqueId { field: Some(index), ..call_id }, returned.depth + 1)
+                } else {
+                    (returned.shared_object, returned.depth)
+                };
+                debug!("[STM] {:?} returns allocation {:?}", callee, shared_object);
+                Allocation { shared_object, root: call_id, depth, returned: Some(returned) }
+            })
+            .collect()
+    }
+
+    /// Return the shared objects read or borrowed from a static by the assignment at
+    /// `location`. A static is a shared object of its own, named after it by
+    /// `static_object`. A constant such as the key of a `thread_local!` is borrowed
+    /// through a promoted static, and holds the shared objects returned by the functions
+    /// its value refers to, like the function that gets the value of the current thread.
+    fn static_allocations(
+        &self,
+        place: &Place<'tcx>,
+        rvalue: &Rvalue<'tcx>,
+        location: Location,
+    ) -> Vec<Allocation> {
+        let root = match place.as_local() {
+            Some(local) => self.unique_id(&local, &location, None),
+            None => return vec![],
+        };
+        let read = match rvalue {
+            Rvalue::Use(Operand::Copy(read))
+            | Rvalue::Use(Operand::Move(read))
+            | Rvalue::Ref(_, _, read) => read,
+            Rvalue::Use(Operand::Constant(constant)) => match self.constant_static(constant) {
+                Some(def_id) if contains_shared_object(self.tcx, self.tcx.type_of(def_id)) => {
+                    let shared_object = static_object(def_id);
+                    return vec![Allocation { shared_object, root, depth: 0, returned: None }];
+                }
+                _ => return vec![],
+            },
+            _ => return vec![],
+        };
+        let static_ = match &read.base {
+            PlaceBase::Static(static_) if contains_shared_object(self.tcx, static_.ty) => static_,
+            _ => return vec![],
+        };
+        match static_.kind {
+            StaticKind::Static => {
+                debug!("[STM] {:?} reads static {:?}", root, static_.def_id);
+                let shared_object = static_object(static_.def_id);
+                vec![Allocation { shared_object, root, depth: 0, returned: None }]
+            }
+            StaticKind::Promoted(promoted, _) => {
+                let (_, promoted_bodies) = self.tcx.mir_validated(self.def_id);
+                let promoted_bodies = promoted_bodies.borrow();
+                let mut allocations = vec![];
+                for data in promoted_bodies[promoted].basic_blocks() {
+                    for statement in &data.statements {
+                        let rvalue = match statement.kind {
+                            StatementKind::Assign(box (_, ref rvalue)) => rvalue,
+                            _ => continue,
+                        };
+                        if let Rvalue::Use(Operand::Constant(constant)) = rvalue {
+                            if let ConstValue::Unevaluated(def_id, _) = constant.literal.val {
+                                allocations.extend(self.constant_allocations(def_id, root));
+                            }
+                        }
+                    }
+                }
+                allocations
+            }
+        }
+    }
+
+    /// Return the static that `constant` points to, if it is a reference to one.
+    fn constant_static(&self, constant: &Constant<'tcx>) -> Option<DefId> {
+        if let ConstValue::Scalar(Scalar::Ptr(ptr)) = constant.literal.val {
+            if let Some(GlobalAlloc::Static(def_id)) = self.tcx.alloc_map.lock().get(ptr.alloc_id)
+            {
+                return Some(def_id);
+            }
+        }
+        None
+    }
+
+    /// Return the shared objects held by the constant `const_def_id`, read at `root`:
+    /// those returned by the functions that its initializer passes to calls.
+    fn constant_allocations(&self, const_def_id: DefId, root: UniqueId) -> Vec<Allocation> {
+        if !contains_shared_object(self.tcx, self.tcx.type_of(const_def_id))
+            || !self.tcx.is_mir_available(const_def_id)
+        {
+            return vec![];
+        }
+        let mut allocations = vec![];
+        for data in self.tcx.optimized_mir(const_def_id).basic_blocks() {
+            if let TerminatorKind::Call { args, .. } = &data.terminator().kind {
+                for arg in args {
+                    if let Operand::Constant(constant) = arg {
+                        if let FnDef(def_id, substs) = constant.literal.ty.kind {
+                            debug!("[STM] constant {:?} refers to {:?}", const_def_id, def_id);
+                            let returned = self.returned_allocations(def_id, substs, root, false);
+                            allocations.extend(returned);
+                        }
+                    }
+                }
+            }
+        }
+        allocations
+    }
+
+    /// Trace the shared objects allocated or returned by a call, or read from a static,
+    /// from their root. They all flow from there in the same way, so they are traced
+    /// once, as the first of them, whose uses the others then share.
+    fn trace_allocations(&mut self, allocations: &[Allocation]) {
+        let (first, root) = match allocations.first() {
+            Some(allocation) => (allocation.shared_object, allocation.root),
+            None => return,
+        };
+        self.current_allocation = Some(first);
+        if self.vertices.insert(root) {
+            self.trace(root);
+        } else {
+            let use_set = match self.edges.get(&root) {
+                Some(edges) => self.use_set(edges),
+                None => FxHashSet::default(),
+            };
+            for transaction_use in use_set {
+                self.map_allocation(&transaction_use.shared_object);
+            }
+        }
+        for allocation in &allocations[1..] {
+            let shared_object = allocation.shared_object;
+            if self.escaped.contains(&first) {
+                self.escaped.insert(shared_object);
+            }
+            for uses in self.allocation_set.values_mut() {
+                let shared: Vec<TransactionUse> = uses
+                    .iter()
+                    .filter(|transaction_use| transaction_use.shared_object == first)
+                    .map(|transaction_use| TransactionUse { shared_object, ..*transaction_use })
+                    .collect();
+                uses.extend(shared);
+            }
+        }
+        for allocation in allocations {
+            if let Some(returned) = &allocation.returned {
+                self.current_allocation = Some(allocation.shared_object);
+                self.returned_uses(root, returned);
+            }
+        }
+        self.current_allocation = None;
+    }
+
+    /// Record what a callee did with a shared object before returning it: its uses in the
+    /// callee's transactions, and its escape, which is a write in the transaction the
+    /// call is in, as for `escape`.
+    fn returned_uses(&mut self, call_id: UniqueId, returned: &ReturnedAllocation) {
+        self.summarized_transactions(call_id, &returned.transactions);
+        if returned.escapes {
+            self.escaped.insert(self.current_allocation.unwrap());
+            if self.transaction_map.terminator_to_tx.contains_key(&call_id) {
+                self.is_write.insert(call_id, true);
+                self.map_allocation(&call_id);
+            }
+        }
+    }
+
     pub fn perform(&mut self) -> FxHashMap<(UniqueId, UniqueId), FxHashSet<TransactionUse>> {
         self.transaction_map.perform();
         for (term_id, tx_ids) in &self.transaction_map.terminator_to_tx {
             debug!("[STM] terminator {:?}: tx {:?}", term_id, tx_ids);
         }
         self.visit_body(self.body);
+        self.add_catch_all();
+        self.add_nested_transactions();
         self.allocation_set.clone()
     }
 
@@ -172,13 +521,13 @@ impl<'a, 'tcx> UseDefVisitor<'_, 'tcx> {
 
         for Use { location, .. } in uses {
             debug!("[STM] considering use @ {:?}", location);
-            let use_kind = Self::location_to_use_kind(location, &use_id, self.body);
+            let use_kind = Self::location_to_use_kind(location, &use_id, self.body, self.tcx);
             if use_kind.is_none() {
                 continue;
             }
             match use_kind.unwrap() {
-                UseKind::Local(new_use_local) => {
-                    let new_use_id = self.unique_id(&new_use_local, location, None);
+                UseKind::Local(new_use_local, field) => {
+                    let new_use_id = self.unique_id(&new_use_local, location, field);
                     self.connect(use_id, new_use_id);
                     debug!("[STM] new edge {:?} -> {:?}", use_id.local, new_use_id.local);
                     if !self.vertices.contains(&new_use_id) {
@@ -313,73 +662,234 @@ impl<'a, 'tcx> UseDefVisitor<'_, 'tcx> {
                     self.vertices.insert(borrow_id.clone());
                     debug!("[STM] WRITE, so we're done.");
                 }
+                UseKind::Escape(call_local) => {
+                    let call_id = self.unique_id(&call_local, location, None);
+                    self.escape(use_id, call_id);
+                }
+                UseKind::Sender(call_local) => {
+                    let call_id = self.unique_id(&call_local, location, None);
+                    self.send(use_id, call_id);
+                    self.vertices.insert(call_id);
+                }
+                UseKind::Sent(call_local) => {
+                    let call_id = self.unique_id(&call_local, location, None);
+                    self.send(use_id, call_id);
+                    if self.vertices.insert(call_id) {
+                        self.trace(call_id);
+                        continue;
+                    }
+                    let use_set = match self.edges.get(&call_id) {
+                        Some(edges) => self.use_set(edges),
+                        None => FxHashSet::default(),
+                    };
+                    for transaction_use in use_set {
+                        self.map_allocation(&transaction_use.shared_object);
+                    }
+                }
                 UseKind::Function(fn_local, fn_def_id, arg_index) => {
                     debug!(
                         "[STM] considering {:?} -> fn or closure {:?} w/ relevant index {:?}",
                         use_id, fn_def_id, arg_index
                     );
-                    if fn_def_id.krate != LOCAL_CRATE {
-                        warn!("[STM] non-local def ID {:?}", fn_def_id);
-                        return;
+                    let call_id = self.unique_id(&fn_local, location, None);
+                    if fn_def_id.is_local() && !is_analyzed(self.tcx, fn_def_id) {
+                        debug!("[STM] {:?} is not analyzed, {:?} escapes", fn_def_id, use_id);
+                        self.escape(use_id, call_id);
+                        continue;
                     }
-                    let (body_ref, _) = self.tcx.mir_validated(fn_def_id);
-                    let fn_body = &body_ref.borrow();
-
-                    debug!("[STM] Relevant arg #{:?} has ID {:?}", arg_index, use_id);
-
-                    let fn_id = self.unique_id(&fn_local, location, None);
-                    let tx_ids = self.transaction_map.terminator_to_tx.get(&fn_id);
-                    debug!("[STM] function call is inside transaction {:?}", tx_ids);
-
-                    let fn_arg_index = match use_id.field {
-                        Some(_) => {
-                            debug!("[STM] function is closure, so arg must be 0");
-                            Some(0)
+                    let summary = match self.assumed.get(&fn_def_id) {
+                        Some(summary) => summary.clone(),
+                        None => self.tcx.shared_object_summary(fn_def_id),
+                    };
+                    debug!("[STM] {:?} has summary {:?}", fn_def_id, summary);
+                    // Arguments that aren't summarized field by field are summarized
+                    // as a whole.
+                    let arg = summary
+                        .arg(arg_index, use_id.field)
+                        .or_else(|| use_id.field.and(summary.arg(arg_index, None)));
+                    match arg {
+                        Some(arg) => self.summarized_use(use_id, call_id, arg),
+                        // The summary says the callee doesn't use the argument.
+                        None if fn_def_id.is_local()
+                            || self.tcx.has_shared_object_summaries(fn_def_id.krate) =>
+                        {
+                            self.vertices.insert(call_id);
                         }
+                        // Without a summary, the callee may do anything with it.
                         None => {
-                            debug!("[STM] function is not closure, so arg must be {:?}", arg_index);
-                            Some(arg_index)
+                            debug!("[STM] {:?} has no summary, {:?} escapes", fn_def_id, use_id);
+                            self.escape(use_id, call_id);
                         }
-                    };
+                    }
+                }
+            }
+        }
+    }
 
-                    let fn_tx_map = TransactionMap::new_child(
-                        fn_def_id,
-                        fn_body,
-                        tx_ids.copied(),
-                        self.tcx,
-                        self.transaction_map.terminator_to_tx.clone(),
-                    );
+    /// Record the use of `use_id` by a call, as described by the callee's summary of the
+    /// argument: the call reads or writes it in the transaction it is in, the callee's own
+    /// transactions use it, it escapes, and the result of the call carries it on.
+    fn summarized_use(&'a mut self, use_id: UniqueId, call_id: UniqueId, arg: &ArgSummary) {
+        self.summarized_transactions(call_id, &arg.transactions);
+        if let Some(is_write) = arg.access {
+            self.is_write.insert(call_id, is_write);
+            self.map_allocation(&call_id);
+            self.connect(use_id, call_id);
+        }
+        if arg.returned {
+            self.connect(use_id, call_id);
+            if self.vertices.insert(call_id) {
+                self.trace(call_id);
+            } else {
+                let use_set = match self.edges.get(&call_id) {
+                    Some(edges) => self.use_set(edges),
+                    None => FxHashSet::default(),
+                };
+                for transaction_use in use_set {
+                    self.map_allocation(&transaction_use.shared_object);
+                }
+            }
+        }
+        if arg.escapes {
+            self.escape(use_id, call_id);
+        }
+        self.vertices.insert(call_id);
+    }
 
-                    let mut fn_visitor = UseDefVisitor::new_child(
-                        Some(use_id),
-                        fn_arg_index,
-                        fn_body,
-                        fn_def_id,
-                        fn_tx_map,
-                        &self,
-                    );
+    /// Record the uses of the current allocation in the transactions of the callee of
+    /// `call_id`, as given by its summary.
+    fn summarized_transactions(
+        &mut self,
+        call_id: UniqueId,
+        transactions: &[SummarizedTransactionUse],
+    ) {
+        let allocation = self.current_allocation.unwrap();
+        let outer = self.transaction_map.terminator_to_tx.get(&call_id).cloned();
+        for tx_use in transactions {
+            // Transactions run from inside a transaction are flattened into it. Pseudo
+            // transactions, whose lock and unlock are the same, aren't run by the callee.
+            let tx_ids = match outer {
+                Some(tx_ids) if tx_use.lock != tx_use.unlock => tx_ids,
+                _ => (tx_use.lock, tx_use.unlock),
+            };
+            self.allocation_set
+                .entry(tx_ids)
+                .or_default()
+                .insert(TransactionUse { shared_object: allocation, is_write: tx_use.is_write });
+        }
+    }
 
-                    fn_visitor.perform();
+    /// Record that the current allocation escapes into a call whose callee is unknown.
+    /// The callee may access it right away, as a write in the transaction of the call,
+    /// or keep it and access it from any transaction that calls unknown code, so the
+    /// allocation joins the catch-all conflict set.
+    fn escape(&mut self, use_id: UniqueId, call_id: UniqueId) {
+        self.escaped.insert(self.current_allocation.unwrap());
+        self.connect(use_id, call_id);
+        self.vertices.insert(call_id);
+        // Passing a shared object along outside a transaction isn't an access.
+        if self.transaction_map.terminator_to_tx.contains_key(&call_id) {
+            self.is_write.insert(call_id, true);
+            self.map_allocation(&call_id);
+        }
+    }
 
-                    for (terminator_id, tx_ids) in fn_visitor.transaction_map.terminator_to_tx {
-                        self.transaction_map.terminator_to_tx.insert(terminator_id, tx_ids);
-                    }
-                    for (local_id, use_ids) in fn_visitor.edges {
-                        self.edges.entry(local_id).or_insert(FxHashSet::default()).extend(use_ids);
-                    }
-                    self.vertices.extend(fn_visitor.vertices);
-                    for (tx_ids, alloc_ids) in fn_visitor.allocation_set {
-                        self.allocation_set
-                            .entry(tx_ids)
-                            .or_insert(FxHashSet::default())
-                            .extend(alloc_ids);
+    /// Record that the current allocation is the channel or the value of a call that sends
+    /// on a channel. Both are used by a pseudo transaction named after the call, so the
+    /// value is in the conflict set of the channel, and of whatever its receivers get.
+    /// Sending isn't an access, even inside a transaction.
+    fn send(&mut self, use_id: UniqueId, call_id: UniqueId) {
+        self.sends.insert(call_id);
+        self.is_write.insert(call_id, false);
+        self.map_allocation(&call_id);
+        self.connect(use_id, call_id);
+    }
+
+    /// Add the catch-all conflict set: a pseudo transaction that uses every escaped
+    /// allocation and the catch-all object, which in turn is used by every transaction
+    /// that calls unknown code that could be handed shared objects.
+    fn add_catch_all(&mut self) {
+        let catch_all = match catch_all(self.tcx) {
+            Some(catch_all) => catch_all,
+            None => return,
+        };
+        let catch_all_use = TransactionUse { shared_object: catch_all, is_write: true };
+        // Callees from other crates may have added to the catch-all set through their summaries.
+        if !self.escaped.is_empty() || self.allocation_set.contains_key(&(catch_all, catch_all)) {
+            let escaped = self.escaped.iter().map(|&allocation| TransactionUse {
+                shared_object: allocation,
+                is_write: true,
+            });
+            let uses = self.allocation_set.entry((catch_all, catch_all)).or_default();
+            uses.extend(escaped);
+            uses.insert(catch_all_use);
+        }
+
+        let param_env = self.tcx.param_env(self.def_id);
+        for (block, block_data) in self.body.basic_blocks().iter_enumerated() {
+            let term = block_data.terminator();
+            if let TerminatorKind::Call { func, args, destination, .. } = &term.kind {
+                let resolved = match func.ty(self.body, self.tcx).kind {
+                    FnDef(fn_def_id, substs) => {
+                        resolve_callee(self.tcx, param_env, fn_def_id, substs).is_some()
                     }
-                    for (borrow_id, is_write) in fn_visitor.is_write {
-                        self.is_write.insert(borrow_id, is_write);
+                    _ => false,
+                };
+                let may_pass_shared_objects = args
+                    .iter()
+                    .any(|arg| may_contain_shared_object(self.tcx, arg.ty(self.body, self.tcx)));
+                if resolved || !may_pass_shared_objects {
+                    continue;
+                }
+                let call_local = match local_from_dest(destination) {
+                    Some(call_local) => call_local,
+                    None => continue,
+                };
+                let location = self.body.terminator_loc(block);
+                let call_id = self.unique_id(&call_local, &location, None);
+                if let Some(tx_ids) = self.transaction_map.terminator_to_tx.get(&call_id) {
+                    debug!("[STM] tx {:?} calls unknown code at {:?}", tx_ids, call_id);
+                    self.allocation_set.entry(*tx_ids).or_default().insert(catch_all_use);
+                }
+            }
+        }
+    }
+
+    /// Flatten the transactions run by calls inside a transaction of this body into it.
+    /// Their shared objects are already traced as part of this transaction. In addition,
+    /// both transactions use a pseudo shared object named after the inner lock call, so
+    /// they are in the same conflict set even if neither uses a shared object, and the
+    /// inner lock call takes the lock the thread already holds.
+    fn add_nested_transactions(&mut self) {
+        let param_env = self.tcx.param_env(self.def_id);
+        let mut nested = vec![];
+        for (block, block_data) in self.body.basic_blocks().iter_enumerated() {
+            if let TerminatorKind::Call { func, destination, .. } = &block_data.terminator().kind {
+                let call_local = match local_from_dest(destination) {
+                    Some(call_local) => call_local,
+                    None => continue,
+                };
+                let location = self.body.terminator_loc(block);
+                let call_id = self.unique_id(&call_local, &location, None);
+                let outer = match self.transaction_map.terminator_to_tx.get(&call_id) {
+                    Some(tx_ids) => *tx_ids,
+                    None => continue,
+                };
+                if let FnDef(fn_def_id, substs) = func.ty(self.body, self.tcx).kind {
+                    if let Some(callee) = resolve_callee(self.tcx, param_env, fn_def_id, substs) {
+                        for inner in TransactionMap::transactions_run_by(self.tcx, callee) {
+                            debug!("[STM] tx {:?} runs nested tx {:?}", outer, inner);
+                            nested.push((outer, inner));
+                        }
                     }
                 }
             }
         }
+        for (outer, inner) in nested {
+            let tx_use = TransactionUse { shared_object: inner.0, is_write: false };
+            self.allocation_set.entry(outer).or_default().insert(tx_use);
+            self.allocation_set.entry(inner).or_default().insert(tx_use);
+        }
     }
 
     /// Make an edge from src to dst.
@@ -390,7 +900,14 @@ impl<'a, 'tcx> UseDefVisitor<'_, 'tcx> {
     /// Find the transaction a given borrow is inside, then associate that transaction
     /// with the current allocation.
     fn map_allocation(&mut self, borrow_id: &UniqueId) {
-        if let Some(tx_ids) = self.transaction_map.terminator_to_tx.get(&borrow_id) {
+        if self.sends.contains(borrow_id) {
+            let allocation = self.current_allocation.unwrap();
+            debug!("[STM] allocation {:?} is sent or sent on by {:?}", allocation, borrow_id);
+            self.allocation_set
+                .entry((*borrow_id, *borrow_id))
+                .or_default()
+                .insert(TransactionUse { shared_object: allocation, is_write: false });
+        } else if let Some(tx_ids) = self.transaction_map.terminator_to_tx.get(&borrow_id) {
             let allocation = self.current_allocation.unwrap();
             let is_write = self.is_write.get(borrow_id).unwrap();
             self.allocation_set
@@ -401,24 +918,57 @@ impl<'a, 'tcx> UseDefVisitor<'_, 'tcx> {
                 "[STM] borrow {:?} inside tx {:?} comes from allocation {:?}",
                 borrow_id, tx_ids, allocation
             );
+        } else if let Some(accesses) = &mut self.caller_accesses {
+            let is_write = self.is_write[borrow_id];
+            debug!("[STM] borrow {:?} is in the caller's transaction", borrow_id);
+            accesses.insert(is_write);
         } else {
             warn!("[STM] borrow {:?} is not inside a transaction!", borrow_id);
+            self.report_untransacted(borrow_id);
+        }
+    }
+
+    /// Report an access to a shared object outside of any transaction, with the
+    /// `untransacted_access` lint. The same access may be reached through several
+    /// callers, but identical diagnostics are only emitted once.
+    fn report_untransacted(&self, borrow_id: &UniqueId) {
+        if borrow_id.def_id != self.def_id {
+            return;
         }
+        let source_info = self.body.source_info(borrow_id.location);
+        let lint_root = match self.body.source_scope_local_data {
+            ClearCrossCrate::Set(ref data) => data[source_info.scope].lint_root,
+            ClearCrossCrate::Clear => return,
+        };
+        let span = source_info.span;
+        self.tcx
+            .struct_span_lint_hir(
+                UNTRANSACTED_ACCESS,
+                lint_root,
+                span,
+                "shared object accessed outside of a transaction",
+            )
+            .code(DiagnosticId::Error("E0744".into()))
+            .span_label(span, "accessed without holding a lock")
+            .help("move the access into a `transaction { .. }` block")
+            .emit();
     }
 
     /// Follow these uses through the edges map to their terminal leaves (uses).
     fn use_set(&self, edges: &FxHashSet<UniqueId>) -> FxHashSet<TransactionUse> {
         let mut allocations: FxHashSet<TransactionUse> = FxHashSet::default();
         for edge in edges {
-            if let Some(new_edges) = self.edges.get(edge) {
-                debug!("[STM] -- intermediate edge {:?}", edge);
-                allocations.extend(self.use_set(new_edges));
-            } else {
+            // A call summarized from another crate can both use a shared object and
+            // return it, so it can be a use and an intermediate edge at once.
+            if let Some(is_write) = self.is_write.get(edge) {
                 debug!("[STM] terminal use {:?}", edge);
-                let is_write = self.is_write.get(edge).unwrap();
                 allocations
                     .insert(TransactionUse { shared_object: edge.clone(), is_write: *is_write });
             }
+            if let Some(new_edges) = self.edges.get(edge) {
+                debug!("[STM] -- intermediate edge {:?}", edge);
+                allocations.extend(self.use_set(new_edges));
+            }
         }
         allocations
     }
@@ -428,6 +978,7 @@ impl<'a, 'tcx> UseDefVisitor<'_, 'tcx> {
         location: &Location,
         use_id: &UniqueId,
         body: &'a Body<'tcx>,
+        tcx: TyCtxt<'tcx>,
     ) -> Option<UseKind<'tcx>> {
         let maybe_bb_data = body.basic_blocks().get(location.block);
         if maybe_bb_data.is_none() {
@@ -447,53 +998,28 @@ impl<'a, 'tcx> UseDefVisitor<'_, 'tcx> {
         if index < length {
             let stmt = stmts[index].clone();
             if let StatementKind::Assign(box (ref place, ref rvalue)) = stmt.kind {
-                if let Some(local) = place.local_or_deref_local() {
-                    // Need to check if this local is the same field.
-                    if let Some(use_field) = use_id.field {
-                        debug!(
-                            "[STM] use has field {:?}, so we need to match the field",
-                            use_field
-                        );
-                        return match rvalue {
-                            Rvalue::Use(Operand::Move(ref place)) => {
-                                for elem in place.projection {
-                                    if let ProjectionElem::Field(field, _ty) = elem {
-                                        if field.index() == use_field {
-                                            return Some(UseKind::Local(local));
-                                        }
-                                    }
-                                }
-                                None
-                            }
-                            Rvalue::Ref(region, borrow_kind, place) => {
-                                info!(
-                                    "ref in region {:?} of kind {:?} in place {:?}",
-                                    region, borrow_kind, place
-                                );
-                                for elem in place.projection {
-                                    if let ProjectionElem::Field(field, _ty) = elem {
-                                        if field.index() == use_field {
-                                            return Some(UseKind::Local(local));
-                                        }
-                                    }
-                                }
-                                None
-                            }
-                            Rvalue::Aggregate(box AggregateKind::Closure(..), ops) => {
-                                debug!("[STM] aggregate");
-                                Some(UseKind::ClosureArg(local, ops.clone()))
-                            }
-                            _ => {
-                                warn!("[STM] unknown rvalue {:?}", rvalue);
-                                None
-                            }
-                        };
-                    }
-                    if let Rvalue::Aggregate(box AggregateKind::Closure(..), ops) = rvalue {
+                if let Rvalue::Aggregate(box AggregateKind::Closure(..), ops) = rvalue {
+                    if let Some(local) = place.local_or_deref_local() {
                         debug!("[STM] statement is a closure aggregate w/ ops {:?}", ops);
                         return Some(UseKind::ClosureArg(local, ops.clone()));
                     }
-                    return Some(UseKind::Local(local));
+                }
+                let value_field = match Self::rvalue_field(rvalue, use_id) {
+                    Some(value_field) => value_field,
+                    None => {
+                        debug!("[STM] {:?} doesn't carry {:?}", rvalue, use_id);
+                        return None;
+                    }
+                };
+                if let Some(local) = place.local_or_deref_local() {
+                    return Some(UseKind::Local(local, value_field));
+                }
+                // Aggregates are also built one field at a time.
+                if let (PlaceBase::Local(local), Some(field)) =
+                    (&place.base, Self::first_field(place))
+                {
+                    debug!("[STM] {:?} is stored in field {} of {:?}", use_id, field, local);
+                    return Some(UseKind::Local(*local, Some(field)));
                 }
             }
             warn!("[STM] statement is not an Assign statement!");
@@ -502,26 +1028,56 @@ impl<'a, 'tcx> UseDefVisitor<'_, 'tcx> {
         // index == length, so must be a terminator
         let term = bb_data.terminator.clone().unwrap();
         if let TerminatorKind::Call { func, args, destination, .. } = &term.kind {
-            let func_name = format!("{:?}", func);
-            if UseDefVisitor::is_read(&func_name) || UseDefVisitor::is_tree_find(&func_name) {
+            if let (Some(fn_def_id), Some((dest, _))) = (Self::callee(func), destination) {
                 let local = local_from_dest(destination).unwrap();
-                return Some(UseKind::Read(local));
-            } else if UseDefVisitor::is_write(&func_name) || UseDefVisitor::is_tree_add(&func_name)
-            {
-                let local = local_from_dest(destination).unwrap();
-                return Some(UseKind::Write(local));
-            // TODO: generalize these cases?
-            } else if UseDefVisitor::is_deref(&func_name)
-                || UseDefVisitor::is_vec_deref(&func_name)
-                //|| UseDefVisitor::is_vec_push(&func_name)
-                || UseDefVisitor::is_tree_deref(&func_name)
-                || UseDefVisitor::is_arc_new(&func_name)
-                || UseDefVisitor::is_clone(&func_name)
-                || UseDefVisitor::is_vec_index(&func_name)
-                || UseDefVisitor::is_arc_vec_index(&func_name)
-            {
-                let local = local_from_dest(destination).unwrap();
-                return Some(UseKind::Local(local));
+                match classify_call(tcx, body, fn_def_id, args, dest) {
+                    CallKind::Read => return Some(UseKind::Read(local)),
+                    CallKind::Write => return Some(UseKind::Write(local)),
+                    // The result may wrap the argument, so its fields aren't the same.
+                    CallKind::Flow => return Some(UseKind::Local(local, None)),
+                    CallKind::Insert => {
+                        // Whatever is inserted can later be read back out of the container.
+                        let inserted = args[1..]
+                            .iter()
+                            .any(|arg| UseDefVisitor::get_local(arg) == Some(use_id.local));
+                        if !inserted {
+                            return None;
+                        }
+                        return args
+                            .first()
+                            .and_then(UseDefVisitor::get_local)
+                            .map(|receiver| UseKind::Local(Self::referent(receiver, body), None));
+                    }
+                    // The function or closure gets a reference to what the receiver holds.
+                    CallKind::With if args.first().and_then(UseDefVisitor::get_local)
+                        == Some(use_id.local) =>
+                    {
+                        let param_env = tcx.param_env(use_id.def_id);
+                        return match args.get(1).map(|arg| &arg.ty(body, tcx).kind) {
+                            Some(&Closure(closure_def_id, _)) => {
+                                Some(UseKind::Function(local, closure_def_id, 1))
+                            }
+                            Some(&FnDef(def_id, substs)) => {
+                                match resolve_callee(tcx, param_env, def_id, substs) {
+                                    Some(callee) => Some(UseKind::Function(local, callee, 0)),
+                                    None => Some(UseKind::Escape(local)),
+                                }
+                            }
+                            _ => Some(UseKind::Escape(local)),
+                        };
+                    }
+                    CallKind::Send => {
+                        let position = args
+                            .iter()
+                            .position(|arg| UseDefVisitor::get_local(arg) == Some(use_id.local));
+                        match position {
+                            Some(0) => return Some(UseKind::Sender(local)),
+                            Some(_) => return Some(UseKind::Sent(local)),
+                            None => {}
+                        }
+                    }
+                    CallKind::With | CallKind::Allocation | CallKind::Other => {}
+                }
             }
             debug!("[STM] other terminator {:?}", func);
             if let Operand::Constant(ref constant) = func {
@@ -536,11 +1092,15 @@ impl<'a, 'tcx> UseDefVisitor<'_, 'tcx> {
                                     "the ty is a closure w/ def id {:?}, substs {:?}",
                                     closure_def_id, closure_substs
                                 );
-                                // Closures pack their arguments into a tuple.
+                                // Closures pack their arguments into a tuple, the
+                                // environment they get as their first argument.
                                 if let Some(field) = use_id.field {
+                                    if !Self::is_closure(body, use_id.local, closure_def_id) {
+                                        continue;
+                                    }
                                     debug!("[STM] we care about the closure's {:?}th field", field);
                                     let local = local_from_dest(destination).unwrap();
-                                    return Some(UseKind::Function(local, closure_def_id, field));
+                                    return Some(UseKind::Function(local, closure_def_id, 0));
                                 } else {
                                     warn!(
                                         "this is a closure, so prev use {:?} should put args into a tuple",
@@ -557,103 +1117,126 @@ impl<'a, 'tcx> UseDefVisitor<'_, 'tcx> {
                             }
                             debug!("[STM] we care about the {}th function argument {:?}", i, arg);
                             let local = local_from_dest(destination).unwrap();
-                            return Some(UseKind::Function(local, fn_def_id.clone(), i));
+                            let param_env = tcx.param_env(use_id.def_id);
+                            return match resolve_callee(tcx, param_env, *fn_def_id, fn_substs) {
+                                Some(callee) => Some(UseKind::Function(local, callee, i)),
+                                None => {
+                                    debug!("[STM] can't resolve {:?}", fn_def_id);
+                                    Some(UseKind::Escape(local))
+                                }
+                            };
                         }
                     }
                 }
+            } else if args.iter().any(|arg| UseDefVisitor::get_local(arg) == Some(use_id.local)) {
+                debug!("[STM] {:?} escapes into a call through {:?}", use_id, func);
+                return local_from_dest(destination).map(UseKind::Escape);
             }
         }
         warn!("[STM] loc {:?} has no definition in body?", location);
         None
     }
 
-    /// Return the Local associated with an Operand, if it has one.
-    /// TODO: just return PlaceBase::Local(local)?
-    fn get_local(operand: &Operand<'tcx>) -> Option<Local> {
-        match operand {
-            Operand::Copy(ref place) => place.local_or_deref_local(),
-            Operand::Move(ref place) => place.local_or_deref_local(),
-            Operand::Constant(_) => None,
+    /// Return where the shared objects of `use_id` are in the value of `rvalue`: anywhere
+    /// in it (`Some(None)`), only in field `i` of it (`Some(Some(i))`), or not at all
+    /// (`None`). Aggregates keep the shared objects of each operand in its own field.
+    fn rvalue_field(rvalue: &Rvalue<'tcx>, use_id: &UniqueId) -> Option<Option<usize>> {
+        match rvalue {
+            Rvalue::Use(Operand::Copy(place))
+            | Rvalue::Use(Operand::Move(place))
+            | Rvalue::Ref(_, _, place) => Self::place_field(place, use_id),
+            Rvalue::Aggregate(box kind, operands) => {
+                let index = operands.iter().position(|operand| match operand {
+                    Operand::Copy(place) | Operand::Move(place) => {
+                        Self::place_field(place, use_id).is_some()
+                    }
+                    Operand::Constant(_) => false,
+                })?;
+                match kind {
+                    // A union is built from its single active field.
+                    AggregateKind::Adt(_, _, _, _, Some(active_field)) => Some(Some(*active_field)),
+                    AggregateKind::Adt(..) | AggregateKind::Tuple => Some(Some(index)),
+                    _ => Some(None),
+                }
+            }
+            _ if use_id.field.is_none() => Some(None),
+            _ => {
+                warn!("[STM] unknown rvalue {:?}", rvalue);
+                None
+            }
         }
     }
 
-    /// Check if the function is txcell::TxPtr::<.*>::new.
-    /// e.g. const txcell::TxPtr::<i32>::new()
-    fn is_new(func_name: &str) -> bool {
-        func_name.starts_with("const txcell::TxPtr::<") && func_name.ends_with(">::new")
-    }
-
-    fn is_arc_vec(func_name: &str) -> bool {
-        func_name.starts_with("const std::vec::Vec::<std::sync::Arc<txcell::TxPtr<")
-            && func_name.ends_with(">>>::new")
-    }
-
-    fn is_vec(func_name: &str) -> bool {
-        func_name.starts_with("const std::vec::Vec::<txcell::TxPtr<")
-            && func_name.ends_with(">>::new")
-    }
-
-    fn is_vec_index(func_name: &str) -> bool {
-        // TODO: index on things other than usize?
-        func_name.starts_with("const <std::vec::Vec<txcell::TxPtr<")
-            && func_name.ends_with(">> as std::ops::Index<usize>>::index")
-    }
-
-    fn is_arc_vec_index(func_name: &str) -> bool {
-        func_name.starts_with("const <std::vec::Vec<std::sync::Arc<txcell::TxPtr<")
-            && func_name.ends_with(">>> as std::ops::Index<usize>>::index")
-    }
-
-    fn is_tree(func_name: &str) -> bool {
-        func_name.starts_with("const txcell::tree::BinarySearchTree::<")
-            && func_name.ends_with(">::new")
-    }
-
-    fn is_tree_find(func_name: &str) -> bool {
-        func_name.starts_with("const txcell::tree::BinarySearchTree::<")
-            && func_name.ends_with(">::find")
-    }
-
-    fn is_tree_add(func_name: &str) -> bool {
-        func_name.starts_with("const txcell::tree::BinarySearchTree::<")
-            && func_name.ends_with(">::add")
-    }
-
-    /// Check if the function is a Deref.
-    fn is_deref(func_name: &str) -> bool {
-        func_name.starts_with("const <std::sync::Arc<txcell::TxPtr<")
-            && func_name.ends_with(">> as std::ops::Deref>::deref")
-    }
-
-    /// Check if the function is a Deref.
-    fn is_vec_deref(func_name: &str) -> bool {
-        func_name.starts_with("const <std::sync::Arc<std::vec::Vec<txcell::TxPtr<")
-            && func_name.ends_with(">>> as std::ops::Deref>::deref")
+    /// Return where the shared objects of `use_id` are in the value of `place`, as for
+    /// `rvalue_field`. Fields are numbered at the first field projection of a place,
+    /// through any dereferences, as in `(*self).0` or `(_1 as Some).0`.
+    fn place_field(place: &Place<'tcx>, use_id: &UniqueId) -> Option<Option<usize>> {
+        match place.base {
+            PlaceBase::Local(local) if local == use_id.local => {}
+            _ => return None,
+        }
+        match (use_id.field, Self::first_field(place)) {
+            (None, _) => Some(None),
+            (Some(field), None) => Some(Some(field)),
+            (Some(field), Some(projected)) if projected == field => Some(None),
+            (Some(_), Some(_)) => None,
+        }
     }
 
-    /// Check if the function is a Deref.
-    fn is_tree_deref(func_name: &str) -> bool {
-        func_name.starts_with("const <std::sync::Arc<txcell::tree::BinarySearchTree<")
-            && func_name.ends_with(">> as std::ops::Deref>::deref")
+    /// Return the first field projected out of `place`, if any.
+    fn first_field(place: &Place<'tcx>) -> Option<usize> {
+        place.projection.iter().find_map(|elem| match elem {
+            ProjectionElem::Field(field, _) => Some(field.index()),
+            _ => None,
+        })
     }
 
-    /// Check if the function is an Arc::new.
-    fn is_arc_new(func_name: &str) -> bool {
-        func_name.starts_with("const std::sync::Arc::<") && func_name.ends_with(">::new")
+    /// Check whether `local` is the closure `closure_def_id`, or a reference to it.
+    fn is_closure(body: &'a Body<'tcx>, local: Local, closure_def_id: DefId) -> bool {
+        let mut ty = body.local_decls[local].ty;
+        while let Ref(_, referent, _) = ty.kind {
+            ty = referent;
+        }
+        match ty.kind {
+            Closure(def_id, _) => def_id == closure_def_id,
+            _ => false,
+        }
     }
 
-    /// Check if the function is an Arc::clone.
-    fn is_clone(func_name: &str) -> bool {
-        func_name.ends_with("> as std::clone::Clone>::clone")
+    /// Return the Local associated with an Operand, if it has one.
+    /// TODO: just return PlaceBase::Local(local)?
+    fn get_local(operand: &Operand<'tcx>) -> Option<Local> {
+        match operand {
+            Operand::Copy(ref place) => place.local_or_deref_local(),
+            Operand::Move(ref place) => place.local_or_deref_local(),
+            Operand::Constant(_) => None,
+        }
     }
 
-    /// Check if the function is txcell::TxPtr::<.*>::borrow.
-    fn is_read(func_name: &str) -> bool {
-        func_name.starts_with("const txcell::TxPtr::<") && func_name.ends_with(">::borrow")
+    /// Return the local that `local` borrows, if it is a `&` or `&mut` temporary
+    /// such as the receiver of a method call, and `local` itself otherwise.
+    fn referent(local: Local, body: &'a Body<'tcx>) -> Local {
+        for bb_data in body.basic_blocks() {
+            for stmt in &bb_data.statements {
+                if let StatementKind::Assign(box (ref place, Rvalue::Ref(_, _, ref borrowed))) =
+                    stmt.kind
+                {
+                    if place.as_local() == Some(local) {
+                        return borrowed.local_or_deref_local().unwrap_or(local);
+                    }
+                }
+            }
+        }
+        local
     }
 
-    /// Check if the function is txcell::TxPtr::<.*>::borrow_mut.
-    fn is_write(func_name: &str) -> bool {
-        func_name.starts_with("const txcell::TxPtr::<") && func_name.ends_with(">::borrow_mut")
+    /// Return the DefId of the function called by this operand, if it is a direct call.
+    fn callee(func: &Operand<'tcx>) -> Option<DefId> {
+        if let Operand::Constant(ref constant) = func {
+            if let FnDef(fn_def_id, _substs) = constant.literal.ty.kind {
+                return Some(fn_def_id);
+            }
+        }
+        None
     }
 }
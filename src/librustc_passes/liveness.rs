@@ -541,7 +541,6 @@ fn visit_expr<'tcx>(ir: &mut IrMaps<'tcx>, expr: &'tcx Expr) {
       hir::ExprKind::Yield(..) |
       hir::ExprKind::Type(..) |
       hir::ExprKind::Lock |
-      hir::ExprKind::Unlock |
       hir::ExprKind::Err |
       hir::ExprKind::Path(hir::QPath::TypeRelative(..)) => {
           intravisit::walk_expr(ir, expr);
@@ -1201,7 +1200,6 @@ impl<'a, 'tcx> Liveness<'a, 'tcx> {
             }
 
             hir::ExprKind::Lit(..) | hir::ExprKind::Err | hir::ExprKind::Lock |
-            hir::ExprKind::Unlock |
             hir::ExprKind::Path(hir::QPath::TypeRelative(..)) => {
                 succ
             }
@@ -1418,7 +1416,7 @@ fn check_expr<'tcx>(this: &mut Liveness<'_, 'tcx>, expr: &'tcx Expr) {
         hir::ExprKind::Cast(..) | hir::ExprKind::DropTemps(..) | hir::ExprKind::Unary(..) |
         hir::ExprKind::Ret(..) | hir::ExprKind::Break(..) | hir::ExprKind::Continue(..) |
         hir::ExprKind::Lit(_) | hir::ExprKind::Block(..) | hir::ExprKind::AddrOf(..) |
-        hir::ExprKind::Struct(..) | hir::ExprKind::Repeat(..) | hir::ExprKind::Lock | hir::ExprKind::Unlock |
+        hir::ExprKind::Struct(..) | hir::ExprKind::Repeat(..) | hir::ExprKind::Lock |
         hir::ExprKind::Closure(..) | hir::ExprKind::Path(_) | hir::ExprKind::Yield(..) |
         hir::ExprKind::Box(..) | hir::ExprKind::Type(..) | hir::ExprKind::Err => {}
     }
@@ -264,7 +264,7 @@ impl<'a, 'tcx> FnCtxt<'a, 'tcx> {
                     tcx.types.err
                 }
             }
-            ExprKind::Lock | ExprKind::Unlock => tcx.mk_unit(),
+            ExprKind::Lock => tcx.mk_unit(),
             ExprKind::Ret(ref expr_opt) => {
                 self.check_expr_return(expr_opt.as_deref(), expr)
             }
@@ -60,6 +60,7 @@
 //! or its recursive dependencies.
 
 #![stable(feature = "alloc_module", since = "1.28.0")]
+#![cfg_attr(not(bootstrap), rustc_tx_blocking = "calls the allocator")]
 
 use core::sync::atomic::{AtomicPtr, Ordering};
 use core::{mem, ptr};
@@ -8,6 +8,7 @@
 //! extension traits of `std::os::$platform`.
 
 #![stable(feature = "rust1", since = "1.0.0")]
+#![cfg_attr(not(bootstrap), rustc_tx_blocking = "does file I/O")]
 
 use crate::fmt;
 use crate::ffi::OsString;
@@ -1,4 +1,5 @@
 #![cfg_attr(test, allow(unused))]
+#![cfg_attr(not(bootstrap), rustc_tx_blocking = "does console I/O")]
 
 use crate::io::prelude::*;
 
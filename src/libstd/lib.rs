@@ -311,6 +311,7 @@
 #![feature(toowned_clone_into)]
 #![feature(trace_macros)]
 #![feature(try_reserve)]
+#![cfg_attr(not(bootstrap), feature(tx_container))]
 #![feature(unboxed_closures)]
 #![feature(untagged_unions)]
 #![feature(unwind_attributes)]
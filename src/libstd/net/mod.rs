@@ -27,6 +27,7 @@
 //! [`UdpSocket`]: ../../std/net/struct.UdpSocket.html
 
 #![stable(feature = "rust1", since = "1.0.0")]
+#![cfg_attr(not(bootstrap), rustc_tx_blocking = "does network I/O")]
 
 use crate::io::{self, Error, ErrorKind};
 
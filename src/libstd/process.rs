@@ -105,6 +105,7 @@
 //! [`Read`]: ../io/trait.Read.html
 
 #![stable(feature = "process", since = "1.0.0")]
+#![cfg_attr(not(bootstrap), rustc_tx_blocking = "runs a process")]
 
 use crate::io::prelude::*;
 
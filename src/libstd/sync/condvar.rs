@@ -114,6 +114,7 @@ pub struct Condvar {
     mutex: AtomicUsize,
 }
 
+#[cfg_attr(not(bootstrap), rustc_tx_blocking = "waits for a condition variable")]
 impl Condvar {
     /// Creates a new condition variable which is ready to be waited on and
     /// notified.
@@ -808,6 +808,7 @@ impl<T> Sender<T> {
     /// assert_eq!(tx.send(1).unwrap_err().0, 1);
     /// ```
     #[stable(feature = "rust1", since = "1.0.0")]
+    #[cfg_attr(not(bootstrap), tx_container(send))]
     pub fn send(&self, t: T) -> Result<(), SendError<T>> {
         let (new_inner, ret) = match *unsafe { self.inner() } {
             Flavor::Oneshot(ref p) => {
@@ -961,6 +962,8 @@ impl<T> SyncSender<T> {
     /// assert_eq!(1, msg);
     /// ```
     #[stable(feature = "rust1", since = "1.0.0")]
+    #[cfg_attr(not(bootstrap), tx_container(send))]
+    #[cfg_attr(not(bootstrap), rustc_tx_blocking = "can wait for room in the channel")]
     pub fn send(&self, t: T) -> Result<(), SendError<T>> {
         self.inner.send(t).map_err(SendError)
     }
@@ -1015,6 +1018,7 @@ impl<T> SyncSender<T> {
     /// }
     /// ```
     #[stable(feature = "rust1", since = "1.0.0")]
+    #[cfg_attr(not(bootstrap), tx_container(send))]
     pub fn try_send(&self, t: T) -> Result<(), TrySendError<T>> {
         self.inner.try_send(t)
     }
@@ -1075,6 +1079,7 @@ impl<T> Receiver<T> {
     /// assert!(receiver.try_recv().is_err());
     /// ```
     #[stable(feature = "rust1", since = "1.0.0")]
+    #[cfg_attr(not(bootstrap), tx_container(get))]
     pub fn try_recv(&self) -> Result<T, TryRecvError> {
         loop {
             let new_port = match *unsafe { self.inner() } {
@@ -1182,6 +1187,8 @@ impl<T> Receiver<T> {
     /// assert_eq!(Err(RecvError), recv.recv());
     /// ```
     #[stable(feature = "rust1", since = "1.0.0")]
+    #[cfg_attr(not(bootstrap), tx_container(get))]
+    #[cfg_attr(not(bootstrap), rustc_tx_blocking = "waits for a message")]
     pub fn recv(&self) -> Result<T, RecvError> {
         loop {
             let new_port = match *unsafe { self.inner() } {
@@ -1303,6 +1310,8 @@ impl<T> Receiver<T> {
     /// );
     /// ```
     #[stable(feature = "mpsc_recv_timeout", since = "1.12.0")]
+    #[cfg_attr(not(bootstrap), tx_container(get))]
+    #[cfg_attr(not(bootstrap), rustc_tx_blocking = "waits for a message")]
     pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
         // Do an optimistic try_recv to avoid the performance impact of
         // Instant::now() in the full-channel case.
@@ -1378,6 +1387,7 @@ impl<T> Receiver<T> {
     /// );
     /// ```
     #[unstable(feature = "deadline_api", issue = "46316")]
+    #[cfg_attr(not(bootstrap), tx_container(get))]
     pub fn recv_deadline(&self, deadline: Instant) -> Result<T, RecvTimeoutError> {
         use self::RecvTimeoutError::*;
 
@@ -1456,6 +1466,8 @@ impl<T> Receiver<T> {
     /// assert_eq!(iter.next(), None);
     /// ```
     #[stable(feature = "rust1", since = "1.0.0")]
+    #[cfg_attr(not(bootstrap), tx_container(project))]
+    #[cfg_attr(not(bootstrap), rustc_tx_blocking = "waits for messages")]
     pub fn iter(&self) -> Iter<'_, T> {
         Iter { rx: self }
     }
@@ -1499,6 +1511,7 @@ impl<T> Receiver<T> {
     /// assert_eq!(iter.next(), None);
     /// ```
     #[stable(feature = "receiver_try_iter", since = "1.15.0")]
+    #[cfg_attr(not(bootstrap), tx_container(project))]
     pub fn try_iter(&self) -> TryIter<'_, T> {
         TryIter { rx: self }
     }
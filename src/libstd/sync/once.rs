@@ -214,6 +214,7 @@ impl Once {
     ///
     /// [poison]: struct.Mutex.html#poisoning
     #[stable(feature = "rust1", since = "1.0.0")]
+    #[cfg_attr(not(bootstrap), rustc_tx_blocking = "can wait for another thread")]
     pub fn call_once<F>(&self, f: F) where F: FnOnce() {
         // Fast path check
         if self.is_completed() {
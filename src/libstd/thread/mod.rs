@@ -604,6 +604,7 @@ impl Builder {
 /// [`Send`]: ../../std/marker/trait.Send.html
 /// [`Sync`]: ../../std/marker/trait.Sync.html
 #[stable(feature = "rust1", since = "1.0.0")]
+#[cfg_attr(not(bootstrap), rustc_tx_blocking = "spawns a thread")]
 pub fn spawn<F, T>(f: F) -> JoinHandle<T> where
     F: FnOnce() -> T, F: Send + 'static, T: Send + 'static
 {
@@ -743,6 +744,7 @@ pub fn panicking() -> bool {
 /// ```
 #[stable(feature = "rust1", since = "1.0.0")]
 #[rustc_deprecated(since = "1.6.0", reason = "replaced by `std::thread::sleep`")]
+#[cfg_attr(not(bootstrap), rustc_tx_blocking = "sleeps")]
 pub fn sleep_ms(ms: u32) {
     sleep(Duration::from_millis(ms as u64))
 }
@@ -774,6 +776,7 @@ pub fn sleep_ms(ms: u32) {
 /// assert!(now.elapsed() >= ten_millis);
 /// ```
 #[stable(feature = "thread_sleep", since = "1.4.0")]
+#[cfg_attr(not(bootstrap), rustc_tx_blocking = "sleeps")]
 pub fn sleep(dur: Duration) {
     imp::Thread::sleep(dur)
 }
@@ -878,6 +881,7 @@ const NOTIFIED: usize = 2;
 //   http://cr.openjdk.java.net/~stefank/6989984.1/raw_files/new/src/os/linux/vm/os_linux.cpp
 // or futuxes, and in either case may allow spurious wakeups.
 #[stable(feature = "rust1", since = "1.0.0")]
+#[cfg_attr(not(bootstrap), rustc_tx_blocking = "parks the thread")]
 pub fn park() {
     let thread = current();
 
@@ -975,6 +979,7 @@ pub fn park_timeout_ms(ms: u32) {
 ///
 /// [park]: fn.park.html
 #[stable(feature = "park_timeout", since = "1.4.0")]
+#[cfg_attr(not(bootstrap), rustc_tx_blocking = "parks the thread")]
 pub fn park_timeout(dur: Duration) {
     let thread = current();
 
@@ -1453,6 +1458,7 @@ impl<T> JoinHandle<T> {
     /// join_handle.join().expect("Couldn't join on the associated thread");
     /// ```
     #[stable(feature = "rust1", since = "1.0.0")]
+    #[cfg_attr(not(bootstrap), rustc_tx_blocking = "waits for a thread")]
     pub fn join(mut self) -> Result<T> {
         self.0.join()
     }
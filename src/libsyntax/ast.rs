@@ -1137,7 +1137,7 @@ impl Expr {
             ExprKind::Paren(..) => ExprPrecedence::Paren,
             ExprKind::Try(..) => ExprPrecedence::Try,
             ExprKind::Yield(..) => ExprPrecedence::Yield,
-            ExprKind::Lock | ExprKind::Unlock | ExprKind::Err  => ExprPrecedence::Err,
+            ExprKind::Lock | ExprKind::Err  => ExprPrecedence::Err,
         }
     }
 }
@@ -1290,7 +1290,6 @@ pub enum ExprKind {
 
     /// STM - for txn stuff
     Lock,
-    Unlock,
 
     /// Placeholder for an expression that wasn't syntactically well formed in some way.
     Err,
@@ -939,6 +939,28 @@ pub fn find_transparency(
     (transparency.map_or(fallback, |t| t.0), error)
 }
 
+/// Parses `#[tx_task(period = N, priority = N)]` into the period and the priority of
+/// the task. Returns `None` unless both are given once, as unsuffixed integers, and
+/// the period is greater than 0.
+pub fn find_tx_task(attr: &Attribute) -> Option<(u128, u128)> {
+    let (mut period, mut priority) = (None, None);
+    for item in attr.meta_item_list()? {
+        let value = match item.meta_item().and_then(|meta_item| meta_item.name_value_literal()) {
+            Some(&ast::Lit { kind: ast::LitKind::Int(n, ast::LitIntType::Unsuffixed), .. }) => n,
+            _ => return None,
+        };
+        let slot = match item.name_or_empty() {
+            sym::period if value > 0 => &mut period,
+            sym::priority => &mut priority,
+            _ => return None,
+        };
+        if slot.replace(value).is_some() {
+            return None;
+        }
+    }
+    Some((period?, priority?))
+}
+
 pub fn check_builtin_attribute(
     sess: &ParseSess, attr: &ast::Attribute, name: Symbol, template: AttributeTemplate
 ) {
@@ -533,6 +533,18 @@ declare_features! (
     /// Allows using the `efiapi` ABI.
     (active, abi_efiapi, "1.40.0", Some(65815), None),
 
+    /// Allows `#[tx_container]` to describe types that hold shared objects to
+    /// the transaction analysis.
+    (active, tx_container, "1.40.0", None, None),
+
+    /// Allows `transaction { .. }` blocks, whose bodies run atomically with respect
+    /// to every other transaction that uses the same shared objects.
+    (active, transactions, "1.40.0", None, None),
+
+    /// Allows `#[tx_task]` to mark the real-time tasks of a program for the
+    /// blocking-time analysis of its transactions.
+    (active, tx_task, "1.40.0", None, None),
+
     // -------------------------------------------------------------------------
     // feature-group-end: actual feature gates
     // -------------------------------------------------------------------------
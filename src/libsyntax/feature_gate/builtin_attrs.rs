@@ -330,6 +330,20 @@ pub const BUILTIN_ATTRIBUTES: &[BuiltinAttribute] = &[
     gated!(ffi_returns_twice, Whitelisted, template!(Word), experimental!(ffi_returns_twice)),
     gated!(track_caller, Whitelisted, template!(Word), experimental!(track_caller)),
 
+    // Transactional memory:
+    gated!(
+        tx_container, Whitelisted, template!(Word, List: "get|insert|project|send|with"),
+        experimental!(tx_container),
+    ),
+    gated!(
+        tx_bounded, Whitelisted, template!(Word), transactions,
+        "the `#[tx_bounded]` attribute is an experimental feature",
+    ),
+    gated!(
+        tx_task, Whitelisted, template!(List: "period = N, priority = N"),
+        experimental!(tx_task),
+    ),
+
     // ==========================================================================
     // Internal attributes: Stability, deprecation, and unsafe:
     // ==========================================================================
@@ -494,6 +508,20 @@ pub const BUILTIN_ATTRIBUTES: &[BuiltinAttribute] = &[
             cfg_fn!(rustc_attrs),
         ),
     ),
+    // Marks a method that reads or writes the shared objects it is given,
+    // for the transactional memory def-use analysis.
+    rustc_attr!(
+        rustc_tx_access, Whitelisted, template!(NameValueStr: "read|write"),
+        "the `#[rustc_tx_access]` attribute is used to describe shared objects \
+        to the transaction analysis and will never be stable",
+    ),
+    // Marks a function, or every function of an impl, trait or module, that can
+    // block, for the `blocking_in_transactions` lint.
+    rustc_attr!(
+        rustc_tx_blocking, Whitelisted, template!(NameValueStr: "reason"),
+        "the `#[rustc_tx_blocking]` attribute is used to describe blocking functions \
+        to the `blocking_in_transactions` lint and will never be stable",
+    ),
     (
         sym::no_debug, Whitelisted, template!(Word),
         Gated(
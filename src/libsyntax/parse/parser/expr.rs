@@ -1001,7 +1001,8 @@ impl<'a> Parser<'a> {
                         BlockCheckMode::Unsafe(ast::UserProvided),
                         attrs);
                 }
-                if self.eat_keyword(kw::Transaction) {
+                if self.is_transaction_block() {
+                    assert!(self.eat_keyword(kw::Transaction));
                     return self.parse_transaction_block(lo, attrs);
                 }
                 if self.is_do_catch_block() {
@@ -1704,10 +1705,33 @@ impl<'a> Parser<'a> {
         let (iattrs, body) = self.parse_inner_attrs_and_block()?;
         attrs.extend(iattrs);
         let span = span_lo.to(body.span);
+        self.sess.gated_spans.transactions.borrow_mut().push(span);
 
         Ok(self.mk_expr(span, ExprKind::TransactionBlock(body), attrs))
     }
 
+    /// `transaction` is a contextual keyword: it only starts a block where a struct
+    /// literal `transaction { .. }` couldn't be, or where the braces can't hold its
+    /// fields, so it stays usable as an identifier.
+    pub(super) fn is_transaction_block(&self) -> bool {
+        self.token.is_keyword(kw::Transaction) &&
+        self.look_ahead(1, |t| *t == token::OpenDelim(token::Brace)) &&
+        // Prevent `match transaction { .. }`, `if transaction { .. }`, etc.
+        !self.restrictions.contains(Restrictions::NO_STRUCT_LITERAL) &&
+        // Prevent `transaction { field: .. }`, `transaction { ..base }`, etc.
+        !self.is_transaction_struct_expr()
+    }
+
+    /// Whether the braces after `transaction` hold the fields of a struct literal:
+    /// `{ ident: `, `{ ident, `, `{ ident }` or `{ ..`.
+    fn is_transaction_struct_expr(&self) -> bool {
+        self.look_ahead(2, |t| *t == token::DotDot) ||
+        self.look_ahead(2, |t| t.is_ident() && !t.is_reserved_ident()) &&
+        self.look_ahead(3, |t| {
+            *t == token::Colon || *t == token::Comma || *t == token::CloseDelim(token::Brace)
+        })
+    }
+
     fn is_async_block(&self) -> bool {
         self.token.is_keyword(kw::Async) &&
         (
@@ -62,12 +62,14 @@ impl<'a> Parser<'a> {
         // like a path (1 token), but it fact not a path.
         // `union::b::c` - path, `union U { ... }` - not a path.
         // `crate::b::c` - path, `crate struct S;` - not a path.
+        // `transaction::b::c` - path, `transaction { ... }` - not a path.
         } else if self.token.is_path_start() &&
                   !self.token.is_qpath_start() &&
                   !self.is_union_item() &&
                   !self.is_crate_vis() &&
                   !self.is_auto_trait_item() &&
-                  !self.is_async_fn() {
+                  !self.is_async_fn() &&
+                  !self.is_transaction_block() {
             let path = self.parse_path(PathStyle::Expr)?;
 
             if !self.eat(&token::Not) {
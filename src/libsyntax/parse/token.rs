@@ -169,7 +169,6 @@ pub(crate) fn token_can_begin_expr(ident_token: &Token) -> bool {
             kw::While,
             kw::Yield,
             kw::Static,
-            kw::Transaction,
         ].contains(&ident),
         _=> false,
     }
@@ -2336,11 +2336,6 @@ impl<'a> State<'a> {
                 self.s.word("/*LOCK*/");
                 self.pclose();
             }
-            ast::ExprKind::Unlock => {
-                self.popen();
-                self.s.word("/*UNLOCK*/");
-                self.pclose();
-            }
         }
         self.ann.post(self, AnnNode::Expr(expr));
         self.end();
@@ -829,7 +829,7 @@ pub fn walk_expr<'a, V: Visitor<'a>>(visitor: &mut V, expression: &'a Expr) {
         ExprKind::TryBlock(ref body) => {
             visitor.visit_block(body)
         }
-        ExprKind::Lit(_) | ExprKind::Lock | ExprKind::Unlock | ExprKind::Err => {}
+        ExprKind::Lit(_) | ExprKind::Lock | ExprKind::Err => {}
     }
 
     visitor.visit_expr_post(expression)
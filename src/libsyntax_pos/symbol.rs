@@ -65,7 +65,6 @@ symbols! {
         Unsafe:             "unsafe",
         Use:                "use",
         Where:              "where",
-        Transaction:        "transaction", // Claire Edition only
         While:              "while",
 
         // Keywords that are used in unstable Rust or reserved for future use.
@@ -98,6 +97,7 @@ symbols! {
         Auto:               "auto",
         Catch:              "catch",
         Default:            "default",
+        Transaction:        "transaction", // With `#![feature(transactions)]` only
         Union:              "union",
     }
 
@@ -321,6 +321,7 @@ symbols! {
         generators,
         generic_associated_types,
         generic_param_attrs,
+        get,
         global_allocator,
         global_asm,
         globs,
@@ -360,6 +361,7 @@ symbols! {
         infer_outlives_requirements,
         infer_static_outlives_requirements,
         inline,
+        insert,
         intel,
         into_iter,
         IntoIterator,
@@ -498,6 +500,7 @@ symbols! {
         path,
         pattern_parentheses,
         Pending,
+        period,
         pin,
         Pin,
         pinned,
@@ -512,6 +515,7 @@ symbols! {
         prelude,
         prelude_import,
         primitive,
+        priority,
         proc_dash_macro: "proc-macro",
         proc_macro,
         proc_macro_attribute,
@@ -525,6 +529,7 @@ symbols! {
         proc_macro_non_items,
         proc_macro_path_invoc,
         profiler_runtime,
+        project,
         pub_restricted,
         pushpop_unsafe,
         quad_precision_float,
@@ -539,6 +544,7 @@ symbols! {
         raw_dylib,
         raw_identifiers,
         Ready,
+        read,
         reason,
         recursion_limit,
         reexport_test_harness_main,
@@ -616,6 +622,8 @@ symbols! {
         rustc_reservation_impl,
         rustc_test_marker,
         rustc_then_this_would_need,
+        rustc_tx_access,
+        rustc_tx_blocking,
         rustc_variance,
         rustdoc,
         rustfmt,
@@ -627,6 +635,7 @@ symbols! {
         _Self,
         self_in_typedefs,
         self_struct_ctor,
+        send,
         should_panic,
         simd,
         simd_ffi,
@@ -681,6 +690,7 @@ symbols! {
         trace_macros,
         track_caller,
         trait_alias,
+        transactions,
         transmute,
         transparent,
         transparent_enums,
@@ -691,6 +701,9 @@ symbols! {
         try_trait,
         tt,
         tuple_indexing,
+        tx_bounded,
+        tx_container,
+        tx_task,
         Ty,
         ty,
         type_alias_impl_trait,
@@ -741,6 +754,8 @@ symbols! {
         while_let,
         windows,
         windows_subsystem,
+        with,
+        write,
         Yield,
         zeroed,
     }
@@ -0,0 +1,61 @@
+// revisions: cfail1 cfail2
+// compile-flags: -Z query-dep-graph
+// build-pass
+
+#![allow(warnings)]
+#![feature(lang_items, rustc_attrs, transactions)]
+#![crate_type = "rlib"]
+
+// Here the only thing which changes is where the transaction of `moved` is. The crate's
+// conflict sets change with it, but the locks of `untouched` don't, so its optimized
+// MIR is reused.
+
+#[lang = "tx_ptr"]
+pub struct TxPtr<T: 'static> {
+    value: *mut T,
+}
+
+impl<T> TxPtr<T> {
+    #[lang = "tx_ptr_new"]
+    pub fn new(value: T) -> TxPtr<T> {
+        TxPtr { value: Box::into_raw(Box::new(value)) }
+    }
+
+    #[lang = "tx_ptr_borrow_mut"]
+    pub fn borrow_mut(&self) -> &mut T {
+        unsafe { &mut *self.value }
+    }
+}
+
+#[lang = "transaction_lock"]
+pub fn lock(_id: usize) {}
+
+#[lang = "transaction_unlock"]
+pub fn unlock(_id: usize) {}
+
+#[cfg(cfail1)]
+pub fn moved() {
+    let counter = TxPtr::new(0);
+    transaction {
+        *counter.borrow_mut() += 1;
+    }
+}
+
+#[cfg(cfail2)]
+#[rustc_dirty(label="optimized_mir", cfg="cfail2")]
+pub fn moved() {
+    let counter = TxPtr::new(0);
+    let step = 1;
+    transaction {
+        *counter.borrow_mut() += step;
+    }
+}
+
+#[rustc_clean(label="transaction_locks", cfg="cfail2")]
+#[rustc_clean(label="optimized_mir", cfg="cfail2")]
+pub fn untouched() {
+    let counter = TxPtr::new(0);
+    transaction {
+        *counter.borrow_mut() += 1;
+    }
+}
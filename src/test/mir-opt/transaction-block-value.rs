@@ -0,0 +1,73 @@
+// ignore-wasm32-bare compiled with panic=abort by default
+
+// Test that a `transaction` block that evaluates to a value computes the value
+// into its destination, drops the temporaries of its body (`DropTemps`), and
+// only then releases the lock (bb6), before the value is used (bb7).
+
+#![feature(lang_items, transactions)]
+
+#[lang = "transaction_lock"]
+fn lock(_id: usize) {}
+
+#[lang = "transaction_unlock"]
+fn unlock(_id: usize) {}
+
+struct Guard;
+
+impl Guard {
+    fn len(&self) -> usize {
+        1
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {}
+}
+
+fn consume(_x: usize) {}
+
+fn main() {
+    let x = transaction { Guard.len() };
+    consume(x);
+}
+
+// END RUST SOURCE
+// START rustc.main.SimplifyCfg-initial.after.mir
+//     bb0: {
+//         StorageLive(_1);
+//         StorageLive(_2);
+//         _2 = const lock(const 99usize) -> [return: bb1, unwind: bb2];
+//     }
+//     bb1: {
+//         StorageDead(_2);
+//         StorageLive(_4);
+//         StorageLive(_5);
+//         _5 = Guard;
+//         _4 = &_5;
+//         _1 = const Guard::len(move _4) -> [return: bb3, unwind: bb5];
+//     }
+//     bb2 (cleanup): {
+//         resume;
+//     }
+//     bb3: {
+//         StorageDead(_4);
+//         drop(_5) -> [return: bb6, unwind: bb4];
+//     }
+//     bb4 (cleanup): {
+//         _3 = const unlock(const 99usize) -> bb2;
+//     }
+//     bb5 (cleanup): {
+//         drop(_5) -> bb4;
+//     }
+//     bb6: {
+//         StorageDead(_5);
+//         _3 = const unlock(const 99usize) -> [return: bb7, unwind: bb2];
+//     }
+//     bb7: {
+//         FakeRead(ForLet, _1);
+//         StorageLive(_6);
+//         StorageLive(_7);
+//         _7 = _1;
+//         _6 = const consume(move _7) -> [return: bb8, unwind: bb2];
+//     }
+// END rustc.main.SimplifyCfg-initial.after.mir
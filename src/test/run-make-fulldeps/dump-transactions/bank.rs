@@ -0,0 +1,45 @@
+#![feature(lang_items, transactions)]
+#![crate_type = "rlib"]
+
+#[lang = "tx_ptr"]
+pub struct TxPtr<T: 'static> {
+    value: *mut T,
+}
+
+impl<T> TxPtr<T> {
+    #[lang = "tx_ptr_new"]
+    pub fn new(value: T) -> TxPtr<T> {
+        TxPtr { value: Box::into_raw(Box::new(value)) }
+    }
+
+    #[lang = "tx_ptr_borrow"]
+    pub fn borrow(&self) -> &T {
+        unsafe { &*self.value }
+    }
+
+    #[lang = "tx_ptr_borrow_mut"]
+    pub fn borrow_mut(&self) -> &mut T {
+        unsafe { &mut *self.value }
+    }
+}
+
+#[lang = "transaction_lock"]
+pub fn lock(_id: usize) {}
+
+#[lang = "transaction_unlock"]
+pub fn unlock(_id: usize) {}
+
+pub fn transfer() -> u32 {
+    let account = TxPtr::new(0);
+    transaction {
+        *account.borrow_mut() += 1;
+    }
+    let balance = transaction {
+        *account.borrow()
+    };
+    let log = TxPtr::new(0);
+    transaction {
+        *log.borrow_mut() += balance;
+    }
+    balance
+}
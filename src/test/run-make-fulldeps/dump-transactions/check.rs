@@ -0,0 +1,66 @@
+#![feature(rustc_private)]
+
+extern crate serialize;
+
+use serialize::json::{self, Json};
+use std::{env, fs};
+
+/// Return the transaction of `report` whose block starts on `line` of `bank.rs`.
+fn transaction_at(report: &Json, line: u32) -> &Json {
+    let prefix = format!("bank.rs:{}:", line);
+    let transactions = report["transactions"].as_array().unwrap();
+    let mut found = transactions.iter().filter(|transaction| {
+        transaction["span"].as_string().unwrap().starts_with(&prefix)
+    });
+    let transaction = found.next().expect("no transaction on this line");
+    assert!(found.next().is_none(), "more than one transaction on line {}", line);
+    transaction
+}
+
+/// Check that the only shared object of `transaction` is allocated on `line` of
+/// `bank.rs`, and used with `mode`.
+fn check_shared_object(transaction: &Json, line: u32, mode: &str) {
+    let shared_objects = transaction["shared_objects"].as_array().unwrap();
+    assert_eq!(shared_objects.len(), 1);
+    let object = &shared_objects[0];
+    assert_eq!(object["kind"].as_string(), Some("allocation"));
+    assert_eq!(object["function"].as_string(), Some("transfer"));
+    let span = object["span"].as_string().unwrap();
+    assert!(span.starts_with(&format!("bank.rs:{}:", line)), "allocated at {}", span);
+    assert_eq!(object["mode"].as_string(), Some(mode));
+}
+
+fn main() {
+    let path = env::args().nth(1).unwrap();
+    let report = json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+
+    assert_eq!(report["krate"].as_string(), Some("bank"));
+    assert_eq!(report["transaction_level"].as_u64(), Some(1));
+    let transactions = report["transactions"].as_array().unwrap();
+    assert_eq!(transactions.len(), 3);
+    for transaction in transactions {
+        assert_eq!(transaction["function"].as_string(), Some("transfer"));
+        assert!(transaction["id"].is_u64());
+        assert!(transaction["conflict_set"].is_u64());
+        // Lock 0 is left for the transactions the runtime doesn't know about.
+        assert!(transaction["lock"].as_u64().unwrap() > 0);
+    }
+
+    let deposit = transaction_at(&report, 34);
+    let read = transaction_at(&report, 37);
+    let log = transaction_at(&report, 41);
+
+    assert_eq!(deposit["mode"].as_string(), Some("write"));
+    assert_eq!(read["mode"].as_string(), Some("read"));
+    assert_eq!(log["mode"].as_string(), Some("write"));
+    check_shared_object(deposit, 33, "write");
+    check_shared_object(read, 33, "read");
+    check_shared_object(log, 40, "write");
+
+    // The transactions on the same shared object conflict, and share a lock.
+    assert_eq!(deposit["conflict_set"], read["conflict_set"]);
+    assert_eq!(deposit["lock"], read["lock"]);
+    assert_ne!(deposit["conflict_set"], log["conflict_set"]);
+    assert_ne!(deposit["lock"], log["lock"]);
+    assert_ne!(deposit["id"], read["id"]);
+}
@@ -0,0 +1,30 @@
+// `transaction` is only a keyword with the feature, so it's still an identifier here.
+
+#[cfg(FALSE)]
+fn increment(counter: &mut u32) {
+    transaction { *counter += 1; } //~ ERROR `transaction` blocks are experimental
+}
+
+fn transaction(transaction: u32) -> u32 {
+    transaction + 1
+}
+
+// Braces that hold fields are a struct literal, not a transaction block.
+#[allow(non_camel_case_types)]
+struct transaction {
+    value: u32,
+}
+
+#[tx_bounded] //~ ERROR the `#[tx_bounded]` attribute is an experimental feature
+fn log(_value: u32) {}
+
+fn main() {
+    let transaction = transaction(1);
+    log(transaction);
+    match transaction {
+        _ => {}
+    }
+
+    let value = transaction { value: transaction }.value;
+    log(transaction { value }.value);
+}
@@ -0,0 +1,11 @@
+#![crate_type = "lib"]
+
+#[tx_container] //~ ERROR the `#[tx_container]` attribute is an experimental feature
+pub struct Ring<T>(Vec<T>);
+
+impl<T> Ring<T> {
+    #[tx_container(insert)] //~ ERROR the `#[tx_container]` attribute is an experimental feature
+    pub fn push(&mut self, value: T) {
+        self.0.push(value);
+    }
+}
@@ -0,0 +1,4 @@
+#![crate_type = "lib"]
+
+#[tx_task(period = 10, priority = 1)] //~ ERROR the `#[tx_task]` attribute is an experimental feature
+pub fn worker() {}
@@ -0,0 +1,32 @@
+// run-pass
+// aux-build:tx_runtime.rs
+// compile-flags: -Z allocation-context=1
+// With a call string of one call, the shared objects returned by two calls to the same
+// helper are told apart, so the transactions that use them don't share a lock.
+
+#![feature(transactions)]
+
+extern crate tx_runtime;
+
+use tx_runtime::{lock, TxPtr};
+
+fn new_counter() -> TxPtr<u32> {
+    TxPtr::new(0)
+}
+
+fn main() {
+    let first = new_counter();
+    let second = new_counter();
+    transaction {
+        *first.borrow_mut() += 1;
+    }
+    let first_lock = lock();
+    transaction {
+        *second.borrow_mut() += 1;
+    }
+    assert_ne!(lock(), first_lock);
+    transaction {
+        *first.borrow_mut() += 1;
+    }
+    assert_eq!(lock(), first_lock);
+}
@@ -0,0 +1,17 @@
+// aux-build:tx_runtime.rs
+
+extern crate tx_runtime;
+
+use tx_runtime::TxPtr;
+
+pub trait Counter {
+    /// A default method that writes the shared object it is given.
+    fn increment(&self, count: TxPtr<u32>) {
+        *count.borrow_mut() += 1;
+    }
+}
+
+/// Return a closure that writes the shared object it is given.
+pub fn incrementer() -> impl Fn(TxPtr<u32>) {
+    |count| *count.borrow_mut() += 1
+}
@@ -0,0 +1,147 @@
+// A runtime for the transaction tests: a shared object type, and lock functions that
+// record the lock each transaction takes, as given by the lock table of the program.
+
+#![feature(lang_items)]
+
+use std::cell::RefCell;
+use std::mem;
+
+/// A shared object. It is only a handle to its value, so it is freely copied.
+#[lang = "tx_ptr"]
+pub struct TxPtr<T: 'static> {
+    value: *mut T,
+}
+
+impl<T> Clone for TxPtr<T> {
+    fn clone(&self) -> TxPtr<T> {
+        *self
+    }
+}
+
+impl<T> Copy for TxPtr<T> {}
+
+unsafe impl<T: Send> Send for TxPtr<T> {}
+unsafe impl<T: Send> Sync for TxPtr<T> {}
+
+impl<T> TxPtr<T> {
+    #[lang = "tx_ptr_new"]
+    pub fn new(value: T) -> TxPtr<T> {
+        TxPtr { value: Box::into_raw(Box::new(value)) }
+    }
+
+    /// A shared object over `value`, for the initializers of statics. `value` must live
+    /// for the rest of the program.
+    pub const unsafe fn from_raw(value: *mut T) -> TxPtr<T> {
+        TxPtr { value }
+    }
+
+    #[lang = "tx_ptr_borrow"]
+    pub fn borrow(&self) -> &T {
+        unsafe { &*self.value }
+    }
+
+    #[lang = "tx_ptr_borrow_mut"]
+    pub fn borrow_mut(&self) -> &mut T {
+        unsafe { &mut *self.value }
+    }
+}
+
+/// A lock call, with the lock it takes. Lock 0 is the global lock, taken by the
+/// transactions that weren't registered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Taken {
+    /// A lock taken at levels 0 and 1.
+    Lock(usize),
+    /// The read side of a lock, at levels 2 and 3.
+    Read(usize),
+    /// The write side of a lock, at levels 2 and 3.
+    Write(usize),
+}
+
+impl Taken {
+    pub fn lock(self) -> usize {
+        match self {
+            Taken::Lock(lock) | Taken::Read(lock) | Taken::Write(lock) => lock,
+        }
+    }
+}
+
+thread_local! {
+    static LOCKS: RefCell<Vec<(usize, usize)>> = RefCell::new(Vec::new());
+    static TAKEN: RefCell<Vec<Taken>> = RefCell::new(Vec::new());
+    static HELD: RefCell<Vec<Taken>> = RefCell::new(Vec::new());
+}
+
+/// Return the lock calls made by this thread since the last call, in order.
+pub fn taken() -> Vec<Taken> {
+    TAKEN.with(|taken| mem::replace(&mut *taken.borrow_mut(), Vec::new()))
+}
+
+/// Return the lock call of the only transaction run since the last call, or `None` if
+/// its lock was elided.
+pub fn taken_once() -> Option<Taken> {
+    let taken = taken();
+    assert!(taken.len() <= 1, "more than one lock taken: {:?}", taken);
+    taken.first().cloned()
+}
+
+/// Return the lock taken by the only transaction run since the last call.
+pub fn lock() -> usize {
+    taken_once().expect("the transaction takes a lock").lock()
+}
+
+/// Return the locks this thread holds, innermost last.
+pub fn held() -> Vec<Taken> {
+    HELD.with(|held| held.borrow().clone())
+}
+
+fn lock_of(id: usize) -> usize {
+    LOCKS.with(|locks| {
+        let locks = locks.borrow();
+        locks.iter().find(|&&(registered, _)| registered == id).map_or(0, |&(_, lock)| lock)
+    })
+}
+
+fn acquire(taken: Taken) {
+    TAKEN.with(|calls| calls.borrow_mut().push(taken));
+    HELD.with(|held| held.borrow_mut().push(taken));
+}
+
+fn release(taken: Taken) {
+    HELD.with(|held| assert_eq!(held.borrow_mut().pop(), Some(taken)));
+}
+
+#[lang = "transaction_register"]
+pub fn register(id: usize, lock: usize) {
+    LOCKS.with(|locks| locks.borrow_mut().push((id, lock)));
+}
+
+#[lang = "transaction_lock"]
+pub fn transaction_lock(id: usize) {
+    acquire(Taken::Lock(lock_of(id)));
+}
+
+#[lang = "transaction_unlock"]
+pub fn transaction_unlock(id: usize) {
+    release(Taken::Lock(lock_of(id)));
+}
+
+#[lang = "transaction_read_lock"]
+pub fn transaction_read_lock(id: usize) {
+    acquire(Taken::Read(lock_of(id)));
+}
+
+#[lang = "transaction_read_unlock"]
+pub fn transaction_read_unlock(id: usize) {
+    release(Taken::Read(lock_of(id)));
+}
+
+#[lang = "transaction_write_lock"]
+pub fn transaction_write_lock(id: usize) {
+    acquire(Taken::Write(lock_of(id)));
+}
+
+#[lang = "transaction_write_unlock"]
+pub fn transaction_write_unlock(id: usize) {
+    release(Taken::Write(lock_of(id)));
+}
@@ -0,0 +1,4 @@
+// compile-flags:-Z transaction-level=4
+// error-pattern:a number from 0 to 3 was expected
+
+fn main() {}
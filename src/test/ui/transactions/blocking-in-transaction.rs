@@ -0,0 +1,45 @@
+// aux-build:tx_runtime.rs
+// A call that can block while a transaction holds its lock is an error, also through
+// a helper, unless the function it goes through is marked `#[tx_bounded]`. Functions
+// that call each other are found to block whichever of them is searched first.
+
+#![feature(transactions)]
+
+extern crate tx_runtime;
+
+use std::thread;
+use std::time::Duration;
+use tx_runtime::TxPtr;
+
+fn nap() {
+    thread::sleep(Duration::from_millis(1));
+}
+
+fn ping(n: u32) {
+    pong(n);
+    thread::sleep(Duration::from_millis(1));
+}
+
+fn pong(n: u32) {
+    if n > 0 {
+        ping(n - 1);
+    }
+}
+
+#[tx_bounded]
+fn bounded_nap() {
+    thread::sleep(Duration::from_millis(1));
+}
+
+fn main() {
+    let counter = TxPtr::new(0);
+    transaction {
+        *counter.borrow_mut() += 1;
+        thread::sleep(Duration::from_millis(1)); //~ ERROR operation that can block
+        nap(); //~ ERROR operation that can block
+        bounded_nap();
+        ping(1); //~ ERROR operation that can block
+        pong(1); //~ ERROR operation that can block
+    }
+    nap();
+}
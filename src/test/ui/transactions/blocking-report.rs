@@ -0,0 +1,41 @@
+// build-pass
+// aux-build:tx_runtime.rs
+// compile-flags: -Z blocking-report -Z transaction-level=2
+// normalize-stderr-test "transaction \d+" -> "transaction ID"
+// normalize-stderr-test "L\(\d+\)" -> "L(ID)"
+// normalize-stderr-test "lock \d+" -> "lock N"
+// A write waits for the write of every other task that writes, with a reader phase
+// before each of them and before its own turn. A read waits for one writer phase and
+// one reader phase, and a transaction on a lock no other task takes never waits.
+// Transaction IDs and locks are hashed and numbered per program, so they're normalized.
+
+#![feature(transactions, tx_task)]
+
+extern crate tx_runtime;
+
+use tx_runtime::TxPtr;
+
+#[tx_task(period = 10, priority = 1)]
+fn sensor(readings: TxPtr<u32>) {
+    transaction { *readings.borrow_mut() += 1; }
+}
+
+#[tx_task(period = 20, priority = 2)]
+fn calibrate(readings: TxPtr<u32>) {
+    transaction { *readings.borrow_mut() = 0; }
+}
+
+#[tx_task(period = 50, priority = 3)]
+fn display(readings: TxPtr<u32>, log: TxPtr<u32>) {
+    let value = transaction { *readings.borrow() };
+    transaction { *readings.borrow_mut() += 2; }
+    transaction { *log.borrow_mut() += value; }
+}
+
+fn main() {
+    let readings = TxPtr::new(0);
+    let log = TxPtr::new(0);
+    sensor(readings);
+    calibrate(readings);
+    display(readings, log);
+}
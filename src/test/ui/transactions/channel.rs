@@ -0,0 +1,40 @@
+// run-pass
+// aux-build:tx_runtime.rs
+// A shared object sent on a channel is in the conflict set of the transactions that use
+// it once it's received. Sending it isn't an access, even inside a transaction.
+
+#![feature(transactions)]
+
+extern crate tx_runtime;
+
+use std::sync::mpsc;
+use tx_runtime::{lock, TxPtr};
+
+fn main() {
+    let sent = TxPtr::new(0);
+    transaction {
+        *sent.borrow_mut() += 1;
+    }
+    let sent_lock = lock();
+
+    let (sender, receiver) = mpsc::channel();
+    let _ = sender.send(sent);
+    let received = receiver.recv().unwrap();
+    transaction {
+        *received.borrow_mut() += 1;
+    }
+    assert_eq!(lock(), sent_lock);
+
+    let other = TxPtr::new(0);
+    let later = TxPtr::new(0);
+    transaction {
+        *other.borrow_mut() += 1;
+        let _ = sender.send(later);
+    }
+    let sending_lock = lock();
+    let received = receiver.recv().unwrap();
+    transaction {
+        *received.borrow_mut() += 1;
+    }
+    assert_ne!(lock(), sending_lock);
+}
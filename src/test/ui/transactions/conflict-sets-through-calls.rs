@@ -0,0 +1,49 @@
+// run-pass
+// aux-build:tx_runtime.rs
+// A shared object is traced through `Arc::clone`, `Deref::deref` and a helper function
+// of the crate, so the transactions that use it through any of them share a lock, and
+// the transactions of an unrelated shared object don't.
+
+#![feature(transactions)]
+
+extern crate tx_runtime;
+
+use std::ops::Deref;
+use std::sync::Arc;
+use tx_runtime::{lock, TxPtr};
+
+fn helper(counter: &TxPtr<u32>) -> TxPtr<u32> {
+    *counter
+}
+
+fn main() {
+    let shared = Arc::new(TxPtr::new(0));
+    let cloned = Arc::clone(&shared);
+    let dereferenced: &TxPtr<u32> = cloned.deref();
+    let other = TxPtr::new(0);
+    let helped = helper(&other);
+
+    transaction {
+        *shared.borrow_mut() += 1;
+    }
+    let shared_lock = lock();
+    transaction {
+        *cloned.borrow_mut() += 1;
+    }
+    assert_eq!(lock(), shared_lock);
+    transaction {
+        *dereferenced.borrow_mut() += 1;
+    }
+    assert_eq!(lock(), shared_lock);
+
+    transaction {
+        *other.borrow_mut() += 1;
+    }
+    let other_lock = lock();
+    transaction {
+        *helped.borrow_mut() += 1;
+    }
+    assert_eq!(lock(), other_lock);
+
+    assert_ne!(shared_lock, other_lock);
+}
@@ -0,0 +1,42 @@
+// run-pass
+// aux-build:tx_runtime.rs
+// A shared object stored in a container of another crate is in the same conflict set as
+// the transactions that use it through the container: `Vec::push` is marked
+// `#[tx_container(insert)]`, and `Extend::extend` takes shared objects and returns none.
+
+#![feature(transactions)]
+
+extern crate tx_runtime;
+
+use tx_runtime::{lock, TxPtr};
+
+fn main() {
+    let pushed = TxPtr::new(0);
+    let mut pushed_into = Vec::new();
+    pushed_into.push(pushed);
+
+    let extended = TxPtr::new(0);
+    let mut extended_into = Vec::new();
+    extended_into.extend(Some(extended));
+
+    transaction {
+        *pushed.borrow_mut() += 1;
+    }
+    let pushed_lock = lock();
+    transaction {
+        *pushed_into[0].borrow_mut() += 1;
+    }
+    assert_eq!(lock(), pushed_lock);
+
+    transaction {
+        *extended.borrow_mut() += 1;
+    }
+    let extended_lock = lock();
+    transaction {
+        *extended_into[0].borrow_mut() += 1;
+    }
+    assert_eq!(lock(), extended_lock);
+
+    assert_ne!(pushed_lock, extended_lock);
+    assert_ne!(pushed_lock, 0);
+}
@@ -0,0 +1,53 @@
+// run-pass
+// aux-build:tx_runtime.rs
+// aux-build:tx_callbacks.rs
+// The summaries of closures and default methods are encoded, so a transaction that
+// calls one from another crate is in the conflict set of the shared object it is given,
+// rather than in the catch-all conflict set of the calls to unknown code.
+
+#![feature(transactions)]
+
+extern crate tx_callbacks;
+extern crate tx_runtime;
+
+use tx_callbacks::{incrementer, Counter};
+use tx_runtime::{lock, TxPtr};
+
+struct Clicks;
+
+impl Counter for Clicks {}
+
+fn main() {
+    let by_method = TxPtr::new(0);
+    let by_closure = TxPtr::new(0);
+    let escaped = TxPtr::new(0);
+    let increment = incrementer();
+    let unknown: &dyn Fn(TxPtr<u32>) = &increment;
+
+    transaction {
+        *by_method.borrow_mut() += 1;
+    }
+    let method_lock = lock();
+    transaction {
+        Clicks.increment(by_method);
+    }
+    assert_eq!(lock(), method_lock);
+
+    transaction {
+        *by_closure.borrow_mut() += 1;
+    }
+    let closure_lock = lock();
+    transaction {
+        increment(by_closure);
+    }
+    assert_eq!(lock(), closure_lock);
+
+    transaction {
+        unknown(escaped);
+    }
+    let catch_all_lock = lock();
+
+    assert_ne!(method_lock, closure_lock);
+    assert_ne!(closure_lock, catch_all_lock);
+    assert_ne!(method_lock, catch_all_lock);
+}
@@ -0,0 +1,21 @@
+// build-pass
+// aux-build:tx_runtime.rs
+// A transaction that uses no shared objects takes no lock, and is warned about.
+
+#![feature(transactions)]
+
+extern crate tx_runtime;
+
+use tx_runtime::TxPtr;
+
+fn main() {
+    let counter = TxPtr::new(0);
+    transaction {
+        *counter.borrow_mut() += 1;
+    }
+    let mut local = 0;
+    transaction { //~ WARN transaction uses no shared objects
+        local += 1;
+    }
+    assert_eq!(local, 1);
+}
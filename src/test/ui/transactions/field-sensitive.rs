@@ -0,0 +1,77 @@
+// run-pass
+// aux-build:tx_runtime.rs
+// Shared objects stored in different fields of a struct or a tuple are traced apart, so
+// the transactions that use one field don't share a lock with those that use the other.
+// A shared object moved out of a field by a pattern, or borrowed from a field of `self`
+// in a method, is the one stored there.
+
+#![feature(transactions)]
+
+extern crate tx_runtime;
+
+use tx_runtime::{lock, TxPtr};
+
+struct Pair {
+    left: TxPtr<u32>,
+    right: TxPtr<u32>,
+}
+
+impl Pair {
+    fn increment_left(&self) {
+        transaction {
+            *self.left.borrow_mut() += 1;
+        }
+    }
+}
+
+enum Slots {
+    Empty,
+    Both(TxPtr<u32>, TxPtr<u32>),
+}
+
+fn main() {
+    let pair = Pair { left: TxPtr::new(0), right: TxPtr::new(0) };
+    transaction {
+        *pair.left.borrow_mut() += 1;
+    }
+    let left_lock = lock();
+    transaction {
+        *pair.right.borrow_mut() += 1;
+    }
+    let right_lock = lock();
+    assert_ne!(left_lock, right_lock);
+
+    pair.increment_left();
+    assert_eq!(lock(), left_lock);
+
+    let Pair { left, .. } = pair;
+    transaction {
+        *left.borrow_mut() += 1;
+    }
+    assert_eq!(lock(), left_lock);
+
+    let tuple = (TxPtr::new(0), TxPtr::new(0));
+    transaction {
+        *tuple.0.borrow_mut() += 1;
+    }
+    let first_lock = lock();
+    transaction {
+        *tuple.1.borrow_mut() += 1;
+    }
+    assert_ne!(lock(), first_lock);
+
+    let slots = Slots::Both(TxPtr::new(0), TxPtr::new(0));
+    match slots {
+        Slots::Empty => unreachable!(),
+        Slots::Both(first, second) => {
+            transaction {
+                *first.borrow_mut() += 1;
+            }
+            let first_lock = lock();
+            transaction {
+                *second.borrow_mut() += 1;
+            }
+            assert_ne!(lock(), first_lock);
+        }
+    }
+}
@@ -0,0 +1,39 @@
+// Malformed transaction attributes are reported where they are written, even on
+// functions that are never called.
+
+#![feature(rustc_attrs, tx_container, tx_task)]
+#![crate_type = "lib"]
+
+#[rustc_tx_access = "modify"] //~ ERROR unknown transaction access `modify`
+pub fn modify(_value: &mut u32) {}
+
+#[rustc_tx_access = "read"] //~ ERROR attribute should be applied to a function
+pub struct Reader;
+
+#[tx_container(insert)] //~ ERROR attribute should be applied to a function
+pub struct Queue<T>(Vec<T>);
+
+impl<T> Queue<T> {
+    #[tx_container] //~ ERROR attribute should be applied to a type
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[tx_container(remove)] //~ ERROR malformed `tx_container` attribute on a method
+    pub fn remove(&mut self) -> Option<T> {
+        self.0.pop()
+    }
+
+    #[tx_container(get, insert)] //~ ERROR malformed `tx_container` attribute on a method
+    pub fn replace(&mut self, value: T) -> Option<T> {
+        let last = self.0.pop();
+        self.0.push(value);
+        last
+    }
+}
+
+#[tx_task(period = 0, priority = 1)] //~ ERROR malformed `tx_task` attribute
+pub fn never_due() {}
+
+#[tx_task(period = 10, priority = 1)] //~ ERROR attribute should be applied to a function
+pub struct Job;
@@ -0,0 +1,44 @@
+// run-pass
+// aux-build:tx_runtime.rs
+// A transaction nested in another is flattened into it: a nested block takes no lock of
+// its own, and a function called from inside a transaction takes the lock of the outer
+// transaction again. Either way, the outer transaction's conflict set has the shared
+// objects of the nested one.
+
+#![feature(transactions)]
+
+extern crate tx_runtime;
+
+use tx_runtime::{held, lock, taken, TxPtr};
+
+fn increment(counter: TxPtr<u32>) {
+    transaction {
+        *counter.borrow_mut() += 1;
+    }
+}
+
+fn main() {
+    let outer = TxPtr::new(0);
+    let inner = TxPtr::new(0);
+    transaction {
+        *outer.borrow_mut() += 1;
+        transaction {
+            *inner.borrow_mut() += 1;
+            assert_eq!(held().len(), 1);
+        }
+    }
+    let outer_lock = lock();
+    transaction {
+        *inner.borrow_mut() += 1;
+    }
+    assert_eq!(lock(), outer_lock);
+
+    let counter = TxPtr::new(0);
+    transaction {
+        increment(counter);
+    }
+    let taken = taken();
+    assert_eq!(taken.len(), 2);
+    assert_eq!(taken[0].lock(), taken[1].lock());
+    assert!(held().is_empty());
+}
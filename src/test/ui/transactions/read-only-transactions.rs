@@ -0,0 +1,33 @@
+// run-pass
+// aux-build:tx_runtime.rs
+// compile-flags: -Z transaction-level=3
+// At level 3, a transaction whose conflict set has no writers takes no lock. A
+// transaction that reads its own shared object and writes one passed by its caller is
+// in two conflict sets, and keeps its write lock.
+
+#![feature(transactions)]
+
+extern crate tx_runtime;
+
+use tx_runtime::{taken_once, Taken, TxPtr};
+
+fn add_one(counter: TxPtr<u32>) {
+    let one = TxPtr::new(1);
+    transaction {
+        *counter.borrow_mut() += *one.borrow();
+    }
+}
+
+fn main() {
+    let read_only = TxPtr::new(1);
+    let value = transaction { *read_only.borrow() };
+    assert_eq!(value, 1);
+    assert_eq!(taken_once(), None);
+
+    let counter = TxPtr::new(0);
+    add_one(counter);
+    match taken_once() {
+        Some(Taken::Write(_)) => {}
+        taken => panic!("expected a write lock, not {:?}", taken),
+    }
+}
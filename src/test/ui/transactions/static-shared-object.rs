@@ -0,0 +1,39 @@
+// run-pass
+// aux-build:tx_runtime.rs
+// A static holding a shared object is a shared object of its own, so the transactions
+// that use it in different functions share a lock, and don't share it with an
+// unrelated shared object.
+
+#![feature(transactions)]
+
+extern crate tx_runtime;
+
+use tx_runtime::{lock, TxPtr};
+
+static mut VALUE: u32 = 0;
+static COUNTER: TxPtr<u32> = unsafe { TxPtr::from_raw(&VALUE as *const u32 as *mut u32) };
+
+fn increment() {
+    transaction {
+        *COUNTER.borrow_mut() += 1;
+    }
+}
+
+fn get() -> u32 {
+    transaction {
+        *COUNTER.borrow()
+    }
+}
+
+fn main() {
+    increment();
+    let counter_lock = lock();
+    assert_eq!(get(), 1);
+    assert_eq!(lock(), counter_lock);
+
+    let other = TxPtr::new(0);
+    transaction {
+        *other.borrow_mut() += 1;
+    }
+    assert_ne!(lock(), counter_lock);
+}
@@ -0,0 +1,44 @@
+// edition:2018
+#![feature(generators, lang_items, transactions)]
+
+#[lang = "transaction_lock"]
+fn lock(_id: usize) {}
+
+#[lang = "transaction_unlock"]
+fn unlock(_id: usize) {}
+
+async fn ready() {}
+
+async fn await_in_transaction() {
+    transaction {
+        ready().await; //~ ERROR `.await` is not allowed inside a `transaction` block
+    }
+}
+
+async fn await_after_transaction() {
+    transaction {}
+    ready().await;
+}
+
+fn async_block_in_transaction() {
+    transaction {
+        let _future = async { ready().await };
+    }
+}
+
+fn yield_in_transaction() {
+    let _generator = || {
+        transaction {
+            yield 1; //~ ERROR `yield` is not allowed inside a `transaction` block
+        }
+    };
+}
+
+fn transaction_in_generator() {
+    let _generator = || {
+        transaction {}
+        yield 1;
+    };
+}
+
+fn main() {}
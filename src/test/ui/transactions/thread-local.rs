@@ -0,0 +1,38 @@
+// run-pass
+// aux-build:tx_runtime.rs
+// The shared object of a thread-local key is named after the key, so the transactions
+// that use it through `LocalKey::with` in different places share a lock, and don't share
+// it with an unrelated shared object.
+
+#![feature(transactions)]
+
+extern crate tx_runtime;
+
+use tx_runtime::{lock, TxPtr};
+
+thread_local!(static COUNTER: TxPtr<u32> = TxPtr::new(0));
+
+fn increment() {
+    COUNTER.with(|counter| {
+        transaction {
+            *counter.borrow_mut() += 1;
+        }
+    });
+}
+
+fn main() {
+    increment();
+    let counter_lock = lock();
+    COUNTER.with(|counter| {
+        transaction {
+            *counter.borrow_mut() += 1;
+        }
+    });
+    assert_eq!(lock(), counter_lock);
+
+    let other = TxPtr::new(0);
+    transaction {
+        *other.borrow_mut() += 1;
+    }
+    assert_ne!(lock(), counter_lock);
+}
@@ -0,0 +1,102 @@
+// run-pass
+// compile-flags: -Z transaction-level=0
+// ignore-wasm32-bare compiled with panic=abort by default
+// Every path out of a `transaction` block releases its lock exactly once.
+
+#![feature(lang_items, transactions)]
+
+use std::cell::Cell;
+use std::panic;
+
+thread_local!(static HELD: Cell<usize> = Cell::new(0));
+
+#[lang = "transaction_lock"]
+fn lock(_id: usize) {
+    HELD.with(|held| held.set(held.get() + 1));
+}
+
+#[lang = "transaction_unlock"]
+fn unlock(_id: usize) {
+    HELD.with(|held| {
+        assert!(held.get() > 0, "released a lock that isn't held");
+        held.set(held.get() - 1);
+    });
+}
+
+fn held() -> usize {
+    HELD.with(|held| held.get())
+}
+
+fn fall_through() {
+    transaction {
+        assert_eq!(held(), 1);
+    }
+}
+
+fn early_return(early: bool) -> u32 {
+    transaction {
+        if early {
+            return 1;
+        }
+    }
+    2
+}
+
+fn question_mark(value: Result<u32, ()>) -> Result<u32, ()> {
+    let value = transaction { value? + 1 };
+    assert_eq!(held(), 0);
+    Ok(value)
+}
+
+fn break_and_continue() {
+    for i in 0..4 {
+        transaction {
+            if i == 1 {
+                continue;
+            }
+            if i == 3 {
+                break;
+            }
+        }
+        assert_eq!(held(), 0);
+    }
+    let mut i = 0;
+    loop {
+        transaction {
+            i += 1;
+            if i == 2 {
+                break;
+            }
+        }
+    }
+}
+
+fn unwind() {
+    let result = panic::catch_unwind(|| {
+        transaction {
+            panic!("panic inside a transaction");
+        }
+    });
+    assert!(result.is_err());
+}
+
+fn main() {
+    fall_through();
+    assert_eq!(held(), 0);
+
+    assert_eq!(early_return(true), 1);
+    assert_eq!(held(), 0);
+    assert_eq!(early_return(false), 2);
+    assert_eq!(held(), 0);
+
+    assert_eq!(question_mark(Ok(1)), Ok(2));
+    assert_eq!(held(), 0);
+    assert_eq!(question_mark(Err(())), Err(()));
+    assert_eq!(held(), 0);
+
+    break_and_continue();
+    assert_eq!(held(), 0);
+
+    unwind();
+    assert_eq!(held(), 0);
+}
@@ -0,0 +1,35 @@
+// run-pass
+// aux-build:tx_runtime.rs
+// A shared object passed to a function of a crate compiled without summaries, here
+// `mem::forget` through a generic helper, escapes: it is in the catch-all conflict set,
+// and the transaction that passes it takes that lock.
+
+#![feature(transactions)]
+
+extern crate tx_runtime;
+
+use std::mem;
+use tx_runtime::{lock, TxPtr};
+
+fn forget<T>(value: T) {
+    mem::forget(value)
+}
+
+fn main() {
+    let forgotten = TxPtr::new(0);
+    let escaped = TxPtr::new(0);
+    let unknown: &dyn Fn(TxPtr<u32>) = &|_| {};
+
+    transaction {
+        unknown(escaped);
+    }
+    let catch_all_lock = lock();
+    transaction {
+        forget(forgotten);
+    }
+    assert_eq!(lock(), catch_all_lock);
+    transaction {
+        *forgotten.borrow_mut() += 1;
+    }
+    assert_eq!(lock(), catch_all_lock);
+}
@@ -0,0 +1,42 @@
+// aux-build:tx_runtime.rs
+// Reading or writing a shared object outside of a transaction is an error, also when a
+// helper does it with its argument, unless the `untransacted_access` lint is allowed.
+// It is reported like any other lint.
+
+#![feature(transactions)]
+
+extern crate tx_runtime;
+
+use tx_runtime::TxPtr;
+
+fn increment(counter: TxPtr<u32>) {
+    *counter.borrow_mut() += 1;
+}
+
+fn outside_transaction() {
+    let counter = TxPtr::new(0);
+    *counter.borrow_mut() += 1; //~ ERROR shared object accessed outside of a transaction
+    increment(counter); //~ ERROR shared object accessed outside of a transaction
+    #[deny(untransacted_access)]
+    let _value = *counter.borrow(); //~ ERROR shared object accessed outside of a transaction
+}
+
+fn inside_transaction() {
+    let counter = TxPtr::new(0);
+    transaction {
+        *counter.borrow_mut() += 1;
+        increment(counter);
+    }
+}
+
+#[allow(untransacted_access)]
+fn allowed() -> u32 {
+    let counter = TxPtr::new(0);
+    *counter.borrow()
+}
+
+fn main() {
+    outside_transaction();
+    inside_transaction();
+    allowed();
+}